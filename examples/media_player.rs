@@ -1,4 +1,3 @@
-use env_logger;
 use mpvipc::{Error, Event, Mpv, MpvDataType, Property};
 use std::io::{self, Write};
 
@@ -16,8 +15,8 @@ fn main() -> Result<(), Error> {
 
     let mut mpv = Mpv::connect("/tmp/mpv.sock")?;
     let mut pause = false;
-    let mut playback_time = std::f64::NAN;
-    let mut duration = std::f64::NAN;
+    let mut playback_time = f64::NAN;
+    let mut duration = f64::NAN;
     mpv.observe_property(1, "path")?;
     mpv.observe_property(2, "pause")?;
     mpv.observe_property(3, "playback-time")?;
@@ -31,9 +30,9 @@ fn main() -> Result<(), Error> {
                 Property::Path(None) => (),
                 Property::Pause(value) => pause = value,
                 Property::PlaybackTime(Some(value)) => playback_time = value,
-                Property::PlaybackTime(None) => playback_time = std::f64::NAN,
+                Property::PlaybackTime(None) => playback_time = f64::NAN,
                 Property::Duration(Some(value)) => duration = value,
-                Property::Duration(None) => duration = std::f64::NAN,
+                Property::Duration(None) => duration = f64::NAN,
                 Property::Metadata(Some(value)) => {
                     println!("File tags:[K");
                     if let Some(MpvDataType::String(value)) = value.get("ARTIST") {
@@ -50,10 +49,10 @@ fn main() -> Result<(), Error> {
                     }
                 }
                 Property::Metadata(None) => (),
-                Property::Unknown { name: _, data: _ } => (),
+                _ => (),
             },
             Event::Shutdown => return Ok(()),
-            Event::Unimplemented => panic!("Unimplemented event"),
+            Event::Unimplemented { name } => panic!("Unimplemented event '{}'", name),
             _ => (),
         }
         print!(
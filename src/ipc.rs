@@ -1,7 +1,10 @@
 use super::*;
 use log::{debug, warn};
 use serde_json::json;
+use std::cell::Cell;
 use std::io::prelude::*;
+use std::sync::mpsc::{RecvTimeoutError, Sender};
+use std::time::Instant;
 
 #[derive(Debug)]
 pub struct PlaylistEntry {
@@ -9,6 +12,32 @@ pub struct PlaylistEntry {
     pub filename: String,
     pub title: String,
     pub current: bool,
+    /// The stable `playlist_entry_id` mpv assigned to this entry. Unlike `id`
+    /// (the current index), it does not change when the playlist is reordered.
+    pub playlist_entry_id: Option<usize>,
+}
+
+impl PlaylistEntry {
+    /// Returns the title, or the last component of the filename if mpv has not
+    /// resolved a title (yet). For URLs the query and fragment are left out, so
+    /// `https://example.com/videos/clip.mp4?t=10` is shown as `clip.mp4`.
+    pub fn display_title(&self) -> String {
+        if !self.title.is_empty() {
+            return self.title.clone();
+        }
+        let mut name = self.filename.as_str();
+        if name.contains("://") {
+            name = name.split(['?', '#']).next().unwrap_or(name);
+        }
+        let trimmed = name.trim_end_matches(['/', '\\']);
+        let basename = trimmed.rsplit(['/', '\\']).next().unwrap_or(trimmed);
+        if basename.is_empty() || basename.ends_with(':') {
+            // nothing left to shorten to, e.g. for "/" or "C:\\"
+            self.filename.clone()
+        } else {
+            basename.to_string()
+        }
+    }
 }
 
 pub trait TypeHandler: Sized {
@@ -103,7 +132,9 @@ impl TypeHandler for usize {
             if let Value::String(ref error) = map["error"] {
                 if error == "success" && map.contains_key("data") {
                     if let Value::Number(ref num) = map["data"] {
-                        Ok(num.as_u64().unwrap() as usize)
+                        num.as_u64()
+                            .map(|n| n as usize)
+                            .ok_or(Error(ErrorCode::ValueDoesNotContainUsize))
                     } else {
                         Err(Error(ErrorCode::ValueDoesNotContainUsize))
                     }
@@ -123,6 +154,32 @@ impl TypeHandler for usize {
     }
 }
 
+impl TypeHandler for i64 {
+    fn get_value(value: Value) -> Result<i64, Error> {
+        if let Value::Object(map) = value {
+            if let Value::String(ref error) = map["error"] {
+                if error == "success" && map.contains_key("data") {
+                    if let Value::Number(ref num) = map["data"] {
+                        num.as_i64().ok_or(Error(ErrorCode::ValueDoesNotContainI64))
+                    } else {
+                        Err(Error(ErrorCode::ValueDoesNotContainI64))
+                    }
+                } else {
+                    Err(Error(ErrorCode::MpvError(error.to_string())))
+                }
+            } else {
+                Err(Error(ErrorCode::UnexpectedValue))
+            }
+        } else {
+            Err(Error(ErrorCode::UnexpectedValue))
+        }
+    }
+
+    fn as_string(&self) -> String {
+        self.to_string()
+    }
+}
+
 impl TypeHandler for HashMap<String, MpvDataType> {
     fn get_value(value: Value) -> Result<HashMap<String, MpvDataType>, Error> {
         if let Value::Object(map) = value {
@@ -175,18 +232,144 @@ impl TypeHandler for Vec<PlaylistEntry> {
     }
 }
 
+impl TypeHandler for Vec<String> {
+    fn get_value(value: Value) -> Result<Vec<String>, Error> {
+        if let Value::Object(map) = value {
+            if let Value::String(ref error) = map["error"] {
+                if error == "success" && map.contains_key("data") {
+                    if let Value::Array(ref strings) = map["data"] {
+                        strings
+                            .iter()
+                            .map(|s| {
+                                s.as_str()
+                                    .map(str::to_string)
+                                    .ok_or(Error(ErrorCode::ValueDoesNotContainString))
+                            })
+                            .collect()
+                    } else {
+                        Err(Error(ErrorCode::ValueDoesNotContainVec))
+                    }
+                } else {
+                    Err(Error(ErrorCode::MpvError(error.to_string())))
+                }
+            } else {
+                Err(Error(ErrorCode::UnexpectedValue))
+            }
+        } else {
+            Err(Error(ErrorCode::UnexpectedValue))
+        }
+    }
+
+    fn as_string(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+impl TypeHandler for Vec<Edition> {
+    fn get_value(value: Value) -> Result<Vec<Edition>, Error> {
+        if let Value::Object(map) = value {
+            if let Value::String(ref error) = map["error"] {
+                if error == "success" && map.contains_key("data") {
+                    if let Value::Array(ref editions) = map["data"] {
+                        Ok(json_array_to_editions(editions))
+                    } else {
+                        Err(Error(ErrorCode::ValueDoesNotContainVec))
+                    }
+                } else {
+                    Err(Error(ErrorCode::MpvError(error.to_string())))
+                }
+            } else {
+                Err(Error(ErrorCode::UnexpectedValue))
+            }
+        } else {
+            Err(Error(ErrorCode::UnexpectedValue))
+        }
+    }
+
+    fn as_string(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+impl TypeHandler for Vec<Track> {
+    fn get_value(value: Value) -> Result<Vec<Track>, Error> {
+        if let Value::Object(map) = value {
+            if let Value::String(ref error) = map["error"] {
+                if error == "success" && map.contains_key("data") {
+                    if let Value::Array(ref tracks) = map["data"] {
+                        Ok(json_array_to_tracks(tracks))
+                    } else {
+                        Err(Error(ErrorCode::ValueDoesNotContainVec))
+                    }
+                } else {
+                    Err(Error(ErrorCode::MpvError(error.to_string())))
+                }
+            } else {
+                Err(Error(ErrorCode::UnexpectedValue))
+            }
+        } else {
+            Err(Error(ErrorCode::UnexpectedValue))
+        }
+    }
+
+    fn as_string(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+impl TypeHandler for Value {
+    fn get_value(value: Value) -> Result<Value, Error> {
+        if let Value::Object(mut map) = value {
+            if let Value::String(ref error) = map["error"] {
+                if error == "success" {
+                    Ok(map.remove("data").unwrap_or(Value::Null))
+                } else {
+                    Err(Error(ErrorCode::MpvError(error.to_string())))
+                }
+            } else {
+                Err(Error(ErrorCode::UnexpectedValue))
+            }
+        } else {
+            Err(Error(ErrorCode::UnexpectedValue))
+        }
+    }
+
+    fn as_string(&self) -> String {
+        self.to_string()
+    }
+}
+
 pub fn get_mpv_property<T: TypeHandler>(instance: &Mpv, property: &str) -> Result<T, Error> {
     let ipc_string = json!({"command": ["get_property", property]});
-    match serde_json::from_str::<Value>(&send_command_sync(instance, ipc_string)) {
-        Ok(val) => T::get_value(val),
-        Err(why) => Err(Error(ErrorCode::JsonParseError(why.to_string()))),
-    }
+    T::get_value(send_command_sync(instance, ipc_string)?)
+}
+
+/// Reads several properties with pipelined requests, so they only cost one round trip.
+/// Each value is `None` if mpv reports the property as unavailable.
+pub fn get_mpv_properties<T: TypeHandler>(
+    instance: &Mpv,
+    properties: &[&str],
+) -> Result<Vec<Option<T>>, Error> {
+    let commands = properties
+        .iter()
+        .map(|property| json!({"command": ["get_property", property]}))
+        .collect();
+    send_commands_sync(instance, commands)?
+        .into_iter()
+        .map(|reply| {
+            if reply["error"] == "property unavailable" {
+                Ok(None)
+            } else {
+                T::get_value(reply).map(Some)
+            }
+        })
+        .collect()
 }
 
 pub fn get_mpv_property_string(instance: &Mpv, property: &str) -> Result<String, Error> {
-    let ipc_string = json!({"command": ["get_property", property]});
-    let val = serde_json::from_str::<Value>(&send_command_sync(instance, ipc_string))
-        .map_err(|why| Error(ErrorCode::JsonParseError(why.to_string())))?;
+    // let mpv do the conversion so the result matches its own formatting ("yes"/"no", ...)
+    let ipc_string = json!({"command": ["get_property_string", property]});
+    let val = send_command_sync(instance, ipc_string)?;
 
     let map = if let Value::Object(map) = val {
         Ok(map)
@@ -200,31 +383,52 @@ pub fn get_mpv_property_string(instance: &Mpv, property: &str) -> Result<String,
         Err(Error(ErrorCode::UnexpectedValue))
     }?;
 
-    let data = if error == "success" {
-        Ok(&map["data"])
-    } else {
-        Err(Error(ErrorCode::MpvError(error.to_string())))
-    }?;
+    if error != "success" {
+        return Err(Error(ErrorCode::MpvError(error.to_string())));
+    }
 
-    match data {
-        Value::Bool(b) => Ok(b.to_string()),
-        Value::Number(ref n) => Ok(n.to_string()),
-        Value::String(ref s) => Ok(s.to_string()),
-        Value::Array(ref array) => Ok(format!("{:?}", array)),
-        Value::Object(ref map) => Ok(format!("{:?}", map)),
-        Value::Null => Err(Error(ErrorCode::MissingValue)),
+    // mpv doesn't say why a property couldn't be read as a string: unknown,
+    // unavailable and unconvertible properties all reply with success and null data
+    match map.get("data") {
+        Some(Value::String(s)) => Ok(s.to_string()),
+        Some(Value::Null) | None => Err(Error(ErrorCode::MissingValue)),
+        Some(_) => Err(Error(ErrorCode::ValueDoesNotContainString)),
     }
 }
 
+/// Reads a flag/choice property (e.g. `loop-file`, which can be `"inf"` or a count)
+/// and reports whether it is switched on. `"no"`, `"false"` and `"0"` are off,
+/// everything else is on.
+pub fn get_mpv_switch(instance: &Mpv, property: &str) -> Result<bool, Error> {
+    get_mpv_property_string(instance, property).map(|value| switch_is_on(&value))
+}
+
+fn switch_is_on(value: &str) -> bool {
+    !matches!(value, "no" | "false" | "0")
+}
+
 pub fn set_mpv_property(instance: &Mpv, property: &str, value: Value) -> Result<(), Error> {
-    let ipc_string = json!({
+    let feedback = send_command_sync(instance, set_property_command(property, &value))?;
+    match feedback["error"].as_str() {
+        Some("success") => Ok(()),
+        Some(error) => Err(set_error(property, &value, error)),
+        None => Err(Error(ErrorCode::UnexpectedResult)),
+    }
+}
+
+pub(crate) fn set_property_command(property: &str, value: &Value) -> Value {
+    json!({
         "command": ["set_property", property, value]
-    });
+    })
+}
 
-    match serde_json::from_str::<Value>(&send_command_sync(instance, ipc_string)) {
-        Ok(_) => Ok(()),
-        Err(why) => Err(Error(ErrorCode::JsonParseError(why.to_string()))),
-    }
+/// The error for a failed `set_property`, naming what was attempted, e.g.
+/// `setting 'volume'=9999: error running command`.
+fn set_error(property: &str, value: &Value, error: &str) -> Error {
+    Error(ErrorCode::MpvError(format!(
+        "setting '{}'={}: {}",
+        property, value, error
+    )))
 }
 
 pub fn run_mpv_command(instance: &Mpv, command: &str, args: &[&str]) -> Result<(), Error> {
@@ -232,6 +436,16 @@ pub fn run_mpv_command(instance: &Mpv, command: &str, args: &[&str]) -> Result<(
 }
 
 pub fn run_mpv_command2(instance: &Mpv, command: &[&str], args: &[&str]) -> Result<(), Error> {
+    run_mpv_command_ret(instance, command, args).map(|_| ())
+}
+
+/// Runs a command like [run_mpv_command2] and returns the `data` of mpv's reply,
+/// or `Value::Null` for commands that don't return anything.
+pub fn run_mpv_command_ret(
+    instance: &Mpv,
+    command: &[&str],
+    args: &[&str],
+) -> Result<Value, Error> {
     let mut ipc_string = json!({
         "command": command
     });
@@ -240,39 +454,308 @@ pub fn run_mpv_command2(instance: &Mpv, command: &[&str], args: &[&str]) -> Resu
             args_array.push(json!(arg));
         }
     }
-    match serde_json::from_str::<Value>(&send_command_sync(instance, ipc_string)) {
-        Ok(feedback) => {
-            if let Value::String(ref error) = feedback["error"] {
-                if error == "success" {
-                    Ok(())
-                } else {
-                    Err(Error(ErrorCode::MpvError(error.to_string())))
-                }
-            } else {
-                Err(Error(ErrorCode::UnexpectedResult))
-            }
+    let mut feedback = send_command_sync(instance, ipc_string)?;
+    if let Value::String(ref error) = feedback["error"] {
+        if error == "success" {
+            Ok(feedback["data"].take())
+        } else {
+            Err(Error(ErrorCode::MpvError(error.to_string())))
         }
-        Err(why) => Err(Error(ErrorCode::JsonParseError(why.to_string()))),
+    } else {
+        Err(Error(ErrorCode::UnexpectedResult))
     }
 }
 
 pub fn observe_mpv_property(instance: &Mpv, id: &isize, property: &str) -> Result<(), Error> {
+    observe_mpv_property_with(instance, "observe_property", id, property)
+}
+
+/// How a property was observed, so it can be observed the same way again.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct Observation {
+    /// `observe_property` or `observe_property_string`.
+    command: &'static str,
+    property: String,
+}
+
+/// Observes the `observations` of a previous connection again, with the same ids.
+pub(crate) fn restore_observations(
+    instance: &Mpv,
+    observations: &HashMap<isize, Observation>,
+) -> Result<(), Error> {
+    let mut observations: Vec<(isize, Observation)> = observations
+        .iter()
+        .map(|(id, observation)| (*id, observation.clone()))
+        .collect();
+    observations.sort();
+    for (id, observation) in &observations {
+        observe_again(instance, *id, observation)?;
+        debug!(
+            "Observing {} as {} again ({})",
+            observation.property, id, observation.command
+        );
+    }
+    Ok(())
+}
+
+/// Observes _property_ with the same command it was observed with before.
+pub(crate) fn observe_again(
+    instance: &Mpv,
+    id: isize,
+    observation: &Observation,
+) -> Result<(), Error> {
+    observe_mpv_property_with(instance, observation.command, &id, &observation.property)
+}
+
+/// Logs the hooks of a previous connection, [Mpv::reconnect] doesn't add them again.
+pub(crate) fn log_lost_hooks(hooks: &HashMap<u64, String>) {
+    let mut hooks: Vec<(&u64, &String)> = hooks.iter().collect();
+    hooks.sort();
+    for (id, name) in hooks {
+        debug!("Not adding hook {} ({}) again", name, id);
+    }
+}
+
+/// Like [observe_mpv_property], but mpv reports the values formatted as strings.
+pub fn observe_mpv_property_string(
+    instance: &Mpv,
+    id: &isize,
+    property: &str,
+) -> Result<(), Error> {
+    observe_mpv_property_with(instance, "observe_property_string", id, property)
+}
+
+fn observe_mpv_property_with(
+    instance: &Mpv,
+    command: &'static str,
+    id: &isize,
+    property: &str,
+) -> Result<(), Error> {
     let ipc_string = json!({
-        "command": ["observe_property", id, property]
+        "command": [command, id, property]
     });
-    match serde_json::from_str::<Value>(&send_command_sync(instance, ipc_string)) {
-        Ok(feedback) => {
-            if let Value::String(ref error) = feedback["error"] {
-                if error == "success" {
-                    Ok(())
-                } else {
-                    Err(Error(ErrorCode::MpvError(error.to_string())))
+    let feedback = send_command_sync(instance, ipc_string)?;
+    if let Value::String(ref error) = feedback["error"] {
+        if error == "success" {
+            instance
+                .observations
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .insert(
+                    *id,
+                    Observation {
+                        command,
+                        property: property.to_string(),
+                    },
+                );
+            Ok(())
+        } else {
+            Err(Error(ErrorCode::MpvError(error.to_string())))
+        }
+    } else {
+        Err(Error(ErrorCode::UnexpectedResult))
+    }
+}
+
+/// Sets _property_ to `value` and waits until the observation `id` reports it. The
+/// first notification of a new observation carries the current value, so `id` must
+/// have been observed right before.
+///
+/// The set command is written without waiting for its reply first, as skipping lines
+/// until the reply arrives could also skip the notification.
+pub(crate) fn set_mpv_property_confirmed(
+    instance: &mut Mpv,
+    id: isize,
+    property: &str,
+    value: Value,
+    timeout: Option<Duration>,
+) -> Result<(), Error> {
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+    wait_for_confirmation(instance, id, property, value, deadline)
+}
+
+fn wait_for_confirmation(
+    instance: &mut Mpv,
+    id: isize,
+    property: &str,
+    value: Value,
+    deadline: Option<Instant>,
+) -> Result<(), Error> {
+    let is_change = |e: &Value| e["event"] == "property-change" && e["id"] == id;
+
+    let initial = loop {
+        let e = read_json_until(instance, deadline)?;
+        if is_change(&e) {
+            break e["data"].clone();
+        }
+        debug!("Skipped: {}", e);
+    };
+    if same_value(&initial, &value) {
+        return Ok(());
+    }
+
+    let mut command = [set_property_command(property, &value)];
+    let request_id = write_commands(instance, &mut command)?[0];
+    let mut replied = false;
+    let mut confirmed = false;
+    while !(replied && confirmed) {
+        let e = read_json_until(instance, deadline)?;
+        if is_change(&e) {
+            confirmed = same_value(&e["data"], &value);
+        } else if e["request_id"].as_u64() == Some(request_id) {
+            match e["error"].as_str() {
+                Some("success") => replied = true,
+                Some(error) => return Err(set_error(property, &value, error)),
+                None => return Err(Error(ErrorCode::UnexpectedResult)),
+            }
+        } else {
+            debug!("Skipped: {}", e);
+        }
+    }
+    Ok(())
+}
+
+/// Loads `file` and waits until mpv reports that it was opened or that it failed,
+/// see [Mpv::load_file_and_wait].
+pub(crate) fn load_file_and_wait(
+    instance: &mut Mpv,
+    file: &str,
+    option: &str,
+    timeout: Option<Duration>,
+) -> Result<(), Error> {
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+    wait_for_file(instance, file, option, deadline)
+}
+
+fn wait_for_file(
+    instance: &mut Mpv,
+    file: &str,
+    option: &str,
+    deadline: Option<Instant>,
+) -> Result<(), Error> {
+    let mut command = [json!({"command": ["loadfile", file, option]})];
+    let request_id = write_commands(instance, &mut command)?[0];
+    // newer mpv versions reply with the id of the new entry, which the events
+    // carry as well, older ones are matched by the next file that starts
+    let mut entry_id = None;
+    let mut replied = false;
+    let mut started = false;
+    loop {
+        let e = read_json_until(instance, deadline)?;
+        let is_entry =
+            |e: &Value| entry_id.is_none() || e["playlist_entry_id"].as_u64() == entry_id;
+        if e["request_id"].as_u64() == Some(request_id) {
+            match e["error"].as_str() {
+                Some("success") => {
+                    replied = true;
+                    entry_id = e["data"]["playlist_entry_id"].as_u64();
                 }
-            } else {
-                Err(Error(ErrorCode::UnexpectedResult))
+                Some(error) => return Err(Error(ErrorCode::MpvError(error.to_string()))),
+                None => return Err(Error(ErrorCode::UnexpectedResult)),
+            }
+            continue;
+        }
+        match e["event"].as_str() {
+            Some("start-file") if replied => started = is_entry(&e),
+            Some("file-loaded") if started => return Ok(()),
+            Some("end-file") if replied && e["reason"] == "error" && is_entry(&e) => {
+                let error = e["file_error"].as_str().unwrap_or("error");
+                return Err(Error(ErrorCode::MpvError(error.to_string())));
+            }
+            _ => debug!("Skipped: {}", e),
+        }
+    }
+}
+
+/// Replaces the playlist with `file`, unpauses and waits until the file ended, see
+/// [Mpv::play_to_end].
+pub(crate) fn play_to_end(
+    instance: &mut Mpv,
+    file: &str,
+    timeout: Option<Duration>,
+) -> Result<EndFileReason, Error> {
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+    wait_for_end(instance, file, deadline)
+}
+
+fn wait_for_end(
+    instance: &mut Mpv,
+    file: &str,
+    deadline: Option<Instant>,
+) -> Result<EndFileReason, Error> {
+    let mut commands = [
+        json!({"command": ["loadfile", file, "replace"]}),
+        json!({"command": ["set_property", "pause", false]}),
+    ];
+    let request_ids = write_commands(instance, &mut commands)?;
+    // like in wait_for_file, the entry is known by its id if mpv replies with it,
+    // otherwise by the next file that starts
+    let mut entry_id = None;
+    let mut replied = false;
+    let mut started = false;
+    loop {
+        let e = read_json_until(instance, deadline)?;
+        if e["request_id"].as_u64() == Some(request_ids[0]) {
+            match e["error"].as_str() {
+                Some("success") => {
+                    replied = true;
+                    entry_id = e["data"]["playlist_entry_id"].as_u64();
+                }
+                Some(error) => return Err(Error(ErrorCode::MpvError(error.to_string()))),
+                None => return Err(Error(ErrorCode::UnexpectedResult)),
+            }
+            continue;
+        }
+        if e["request_id"].as_u64() == Some(request_ids[1]) {
+            match e["error"].as_str() {
+                Some("success") => {}
+                Some(error) => return Err(set_error("pause", &json!(false), error)),
+                None => return Err(Error(ErrorCode::UnexpectedResult)),
             }
+            continue;
+        }
+        let is_entry = |e: &Value| match entry_id {
+            Some(id) => e["playlist_entry_id"].as_u64() == Some(id),
+            None => started,
+        };
+        match e["event"].as_str() {
+            Some("start-file") if replied && entry_id.is_none() => started = true,
+            Some("end-file") if replied && is_entry(&e) => return Ok(end_file_reason(&e)),
+            _ => debug!("Skipped: {}", e),
+        }
+    }
+}
+
+fn end_file_reason(event: &Value) -> EndFileReason {
+    match event["reason"].as_str() {
+        Some("eof") => EndFileReason::Eof,
+        Some("stop") => EndFileReason::Stop,
+        Some("quit") => EndFileReason::Quit,
+        Some("error") => {
+            EndFileReason::Error(event["file_error"].as_str().unwrap_or("error").to_string())
         }
-        Err(why) => Err(Error(ErrorCode::JsonParseError(why.to_string()))),
+        Some("redirect") => EndFileReason::Redirect,
+        _ => EndFileReason::Unknown,
+    }
+}
+
+fn read_json_until(instance: &mut Mpv, deadline: Option<Instant>) -> Result<Value, Error> {
+    let reader = instance
+        .reader
+        .get_mut()
+        .unwrap_or_else(PoisonError::into_inner);
+    let mut response = String::new();
+    reader.read_line_until(&mut response, deadline)?;
+    serde_json::from_str::<Value>(&response)
+        .map_err(|why| Error(ErrorCode::JsonParseError(why.to_string())))
+}
+
+/// Compares two values the way mpv means them: mpv reports e.g. volume as 100.0,
+/// which should still equal `json!(100)`.
+pub(crate) fn same_value(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64() == b.as_f64(),
+        _ => a == b,
     }
 }
 
@@ -280,47 +763,121 @@ pub fn unobserve_mpv_property(instance: &Mpv, id: &isize) -> Result<(), Error> {
     let ipc_string = json!({
         "command": ["unobserve_property", id]
     });
-    match serde_json::from_str::<Value>(&send_command_sync(instance, ipc_string)) {
-        Ok(feedback) => {
-            if let Value::String(ref error) = feedback["error"] {
-                if error == "success" {
-                    Ok(())
-                } else {
-                    Err(Error(ErrorCode::MpvError(error.to_string())))
-                }
-            } else {
-                Err(Error(ErrorCode::UnexpectedResult))
-            }
+    let feedback = send_command_sync(instance, ipc_string)?;
+    if let Value::String(ref error) = feedback["error"] {
+        if error == "success" {
+            instance
+                .observations
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .remove(id);
+            Ok(())
+        } else {
+            Err(Error(ErrorCode::MpvError(error.to_string())))
         }
-        Err(why) => Err(Error(ErrorCode::JsonParseError(why.to_string()))),
+    } else {
+        Err(Error(ErrorCode::UnexpectedResult))
     }
 }
 
-fn try_convert_property(name: &str, id: usize, data: MpvDataType) -> Event {
-    let property = match name {
+fn ab_loop_point(data: MpvDataType) -> Result<Option<f64>, MpvDataType> {
+    match data {
+        MpvDataType::Double(value) => Ok(Some(value)),
+        MpvDataType::Usize(value) => Ok(Some(value as f64)),
+        MpvDataType::I64(value) => Ok(Some(value as f64)),
+        MpvDataType::Null => Ok(None),
+        MpvDataType::String(ref value) if value == "no" => Ok(None),
+        data => Err(data),
+    }
+}
+
+fn try_convert_property(name: &str, data: MpvDataType) -> Property {
+    match name {
         "path" => match data {
             MpvDataType::String(value) => Property::Path(Some(value)),
             MpvDataType::Null => Property::Path(None),
-            _ => unimplemented!(),
+            data => Property::Unknown {
+                name: name.to_string(),
+                data,
+            },
         },
         "pause" => match data {
             MpvDataType::Bool(value) => Property::Pause(value),
-            _ => unimplemented!(),
+            data => Property::Unknown {
+                name: name.to_string(),
+                data,
+            },
         },
         "playback-time" => match data {
             MpvDataType::Double(value) => Property::PlaybackTime(Some(value)),
             MpvDataType::Null => Property::PlaybackTime(None),
-            _ => unimplemented!(),
+            data => Property::Unknown {
+                name: name.to_string(),
+                data,
+            },
         },
         "duration" => match data {
             MpvDataType::Double(value) => Property::Duration(Some(value)),
             MpvDataType::Null => Property::Duration(None),
-            _ => unimplemented!(),
+            data => Property::Unknown {
+                name: name.to_string(),
+                data,
+            },
         },
         "metadata" => match data {
             MpvDataType::HashMap(value) => Property::Metadata(Some(value)),
             MpvDataType::Null => Property::Metadata(None),
-            _ => unimplemented!(),
+            data => Property::Unknown {
+                name: name.to_string(),
+                data,
+            },
+        },
+        "edition" => match data {
+            MpvDataType::Usize(value) => Property::Edition(Some(value as i64)),
+            MpvDataType::I64(value) => Property::Edition(Some(value)),
+            MpvDataType::Null => Property::Edition(None),
+            data => Property::Unknown {
+                name: name.to_string(),
+                data,
+            },
+        },
+        "chapter" => match data {
+            MpvDataType::Usize(value) => Property::Chapter(Some(value as i64)),
+            MpvDataType::I64(value) => Property::Chapter(Some(value)),
+            MpvDataType::Null => Property::Chapter(None),
+            data => Property::Unknown {
+                name: name.to_string(),
+                data,
+            },
+        },
+        // the ab-loop points are either a timestamp or the string "no"
+        "ab-loop-a" => match ab_loop_point(data) {
+            Ok(value) => Property::AbLoopA(value),
+            Err(data) => Property::Unknown {
+                name: name.to_string(),
+                data,
+            },
+        },
+        "ab-loop-b" => match ab_loop_point(data) {
+            Ok(value) => Property::AbLoopB(value),
+            Err(data) => Property::Unknown {
+                name: name.to_string(),
+                data,
+            },
+        },
+        "core-idle" => match data {
+            MpvDataType::Bool(value) => Property::CoreIdle(value),
+            data => Property::Unknown {
+                name: name.to_string(),
+                data,
+            },
+        },
+        "paused-for-cache" => match data {
+            MpvDataType::Bool(value) => Property::PausedForCache(value),
+            data => Property::Unknown {
+                name: name.to_string(),
+                data,
+            },
         },
         _ => {
             warn!("Property {} not implemented", name);
@@ -329,35 +886,59 @@ fn try_convert_property(name: &str, id: usize, data: MpvDataType) -> Event {
                 data,
             }
         }
-    };
-    Event::PropertyChange { id, property }
+    }
 }
 
-pub fn listen(instance: &mut Mpv) -> Result<Event, Error> {
-    let mut e;
-    // sometimes we get responses unrelated to events, so we read a new line until we receive one
-    // with an event field
-    let name = loop {
-        let mut response = String::new();
-        instance.reader.read_line(&mut response).unwrap();
-        response = response.trim_end().to_string();
-        debug!("Event: {}", response);
+/// Decodes the value of _<name>_ the same way as for [Event::PropertyChange].
+pub(crate) fn property_from_json(name: &str, data: &Value) -> Result<Property, Error> {
+    Ok(try_convert_property(name, json_to_data(name, data)?))
+}
 
-        e = serde_json::from_str::<Value>(&response)
-            .map_err(|why| Error(ErrorCode::JsonParseError(why.to_string())))?;
+/// Decodes the value of the property _name_ the way property-change events are.
+pub(crate) fn json_to_data(name: &str, data: &Value) -> Result<MpvDataType, Error> {
+    Ok(match *data {
+        Value::String(ref n) => MpvDataType::String(n.to_string()),
 
-        match e["event"] {
-            Value::String(ref name) => break name,
-            _ => {
-                // It was not an event - try again
-                debug!("Bad response: {:?}", response)
+        Value::Array(ref a) => {
+            if name == "playlist" {
+                MpvDataType::Playlist(Playlist(json_array_to_playlist(a)))
+            } else {
+                MpvDataType::Array(json_array_to_vec(a))
             }
         }
-    };
 
-    let event = match name.as_str() {
+        Value::Bool(b) => MpvDataType::Bool(b),
+
+        Value::Number(ref n) => {
+            if n.is_u64() {
+                MpvDataType::Usize(n.as_u64().unwrap() as usize)
+            } else if n.is_i64() {
+                MpvDataType::I64(n.as_i64().unwrap())
+            } else if n.is_f64() {
+                MpvDataType::Double(n.as_f64().unwrap())
+            } else {
+                return Err(Error(ErrorCode::JsonContainsUnexptectedType));
+            }
+        }
+
+        Value::Object(ref m) => MpvDataType::HashMap(json_map_to_hashmap(m)),
+
+        Value::Null => MpvDataType::Null,
+    })
+}
+
+pub fn listen(instance: &mut Mpv) -> Result<Event, Error> {
+    let e = listen_json(instance)?;
+    event_from_json(instance, e)
+}
+
+fn event_from_json(instance: &Mpv, e: Value) -> Result<Event, Error> {
+    let event = match e["event"].as_str().unwrap_or_default() {
         "shutdown" => Event::Shutdown,
-        "start-file" => Event::StartFile,
+        "start-file" => {
+            let playlist_entry_id = e["playlist_entry_id"].as_u64().map(|id| id as usize);
+            Event::StartFile { playlist_entry_id }
+        }
         "file-loaded" => Event::FileLoaded,
         "seek" => Event::Seek,
         "playback-restart" => Event::PlaybackRestart,
@@ -371,7 +952,9 @@ pub fn listen(instance: &mut Mpv) -> Result<Event, Error> {
         "unpause" => Event::Unpause,
         "metadata-update" => Event::MetadataUpdate,
         "chapter-change" => Event::ChapterChange,
-        "end-file" => Event::EndFile,
+        "end-file" => Event::EndFile {
+            reason: end_file_reason(&e),
+        },
         "property-change" => {
             let name = match e["name"] {
                 Value::String(ref n) => Ok(n.to_string()),
@@ -379,39 +962,30 @@ pub fn listen(instance: &mut Mpv) -> Result<Event, Error> {
             }?;
 
             let id: usize = match e["id"] {
-                Value::Number(ref n) => n.as_u64().unwrap() as usize,
+                Value::Number(ref n) => n.as_u64().unwrap_or(0) as usize,
                 _ => 0,
             };
 
-            let data: MpvDataType = match e["data"] {
-                Value::String(ref n) => MpvDataType::String(n.to_string()),
-
-                Value::Array(ref a) => {
-                    if name == "playlist".to_string() {
-                        MpvDataType::Playlist(Playlist(json_array_to_playlist(a)))
-                    } else {
-                        MpvDataType::Array(json_array_to_vec(a))
-                    }
-                }
-
-                Value::Bool(b) => MpvDataType::Bool(b),
-
-                Value::Number(ref n) => {
-                    if n.is_u64() {
-                        MpvDataType::Usize(n.as_u64().unwrap() as usize)
-                    } else if n.is_f64() {
-                        MpvDataType::Double(n.as_f64().unwrap())
-                    } else {
-                        return Err(Error(ErrorCode::JsonContainsUnexptectedType));
-                    }
-                }
-
-                Value::Object(ref m) => MpvDataType::HashMap(json_map_to_hashmap(m)),
-
-                Value::Null => MpvDataType::Null,
-            };
-
-            try_convert_property(name.as_ref(), id, data)
+            let property = property_from_json(&name, &e["data"])?;
+            Event::PropertyChange { id, property }
+        }
+        "hook" => {
+            // mpv only sends the id passed to hook-add, so look the name up ourselves
+            let name = e["id"]
+                .as_u64()
+                .and_then(|id| {
+                    instance
+                        .hooks
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .get(&id)
+                        .cloned()
+                })
+                .unwrap_or_default();
+            let hook_id = e["hook_id"]
+                .as_u64()
+                .ok_or(Error(ErrorCode::JsonContainsUnexptectedType))?;
+            Event::Hook { name, hook_id }
         }
         "client-message" => {
             let args = match e["args"] {
@@ -424,44 +998,545 @@ pub fn listen(instance: &mut Mpv) -> Result<Event, Error> {
                     .collect::<Result<Vec<_>, _>>(),
                 _ => return Err(Error(ErrorCode::JsonContainsUnexptectedType)),
             }?;
-            Event::ClientMessage { args }
+            key_binding_from_args(&args).unwrap_or(Event::ClientMessage { args })
         }
-        _ => Event::Unimplemented,
+        name => Event::Unimplemented {
+            name: name.to_string(),
+        },
     };
     Ok(event)
 }
 
-pub fn listen_raw(instance: &mut Mpv) -> String {
+/// Parses the `client-message` mpv sends for `script-binding`, with the arguments
+/// `key-binding`, the binding name, the state (e.g. `d-`) and the key name.
+fn key_binding_from_args(args: &[String]) -> Option<Event> {
+    if args.first()? != "key-binding" {
+        return None;
+    }
+    let state = match args.get(2)?.chars().next()? {
+        'd' => KeyState::Down,
+        'u' => KeyState::Up,
+        'r' => KeyState::Repeat,
+        'p' => KeyState::Press,
+        _ => return None,
+    };
+    Some(Event::KeyBinding {
+        name: args[1].clone(),
+        state,
+        key_name: args.get(3).cloned(),
+    })
+}
+
+/// Reads the events mpv already sent without waiting for more, see
+/// [EventDispatcher::dispatch_pending]. Returns the events that could be read along
+/// with the first error, so a broken line or connection doesn't lose the events
+/// read before it.
+#[cfg(unix)]
+pub(crate) fn read_pending_events(instance: &mut Mpv) -> (Vec<Event>, Option<Error>) {
+    let reader = instance
+        .reader
+        .get_mut()
+        .unwrap_or_else(PoisonError::into_inner);
+    let mut lines = Vec::new();
+    let mut first_error = loop {
+        let mut line = String::new();
+        match reader.read_ready_line(&mut line) {
+            Ok(true) => lines.push(line),
+            Ok(false) => break None,
+            Err(error) => break Some(error),
+        }
+    };
+
+    let mut events = Vec::with_capacity(lines.len());
+    for line in lines {
+        debug!("Event: {}", line.trim_end());
+        let event = serde_json::from_str::<Value>(&line)
+            .map_err(|why| Error(ErrorCode::JsonParseError(why.to_string())))
+            .and_then(|e| {
+                if e["event"].is_string() {
+                    event_from_json(instance, e).map(Some)
+                } else {
+                    debug!("Skipped: {}", e);
+                    Ok(None)
+                }
+            });
+        match event {
+            Ok(event) => events.extend(event),
+            Err(error) => {
+                first_error.get_or_insert(error);
+            }
+        }
+    }
+    (events, first_error)
+}
+
+pub fn listen_json(instance: &mut Mpv) -> Result<Value, Error> {
+    // sometimes we get responses unrelated to events, so we read a new line until we receive one
+    // with an event field
+    loop {
+        let mut response = String::new();
+        match instance
+            .reader
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+            .read_line(&mut response)
+        {
+            // a command on another handle briefly set a read timeout on the shared socket
+            Err(Error(ErrorCode::Timeout)) => continue,
+            result => result?,
+        }
+        response = response.trim_end().to_string();
+        debug!("Event: {}", response);
+
+        let e = serde_json::from_str::<Value>(&response)
+            .map_err(|why| Error(ErrorCode::JsonParseError(why.to_string())))?;
+
+        match e["event"] {
+            Value::String(_) => return Ok(e),
+            _ => {
+                // It was not an event - try again
+                debug!("Bad response: {:?}", response)
+            }
+        }
+    }
+}
+
+pub fn listen_raw(instance: &mut Mpv) -> Result<String, Error> {
     let mut response = String::new();
-    instance.reader.read_line(&mut response).unwrap();
-    response.trim_end().to_string()
+    loop {
+        match instance
+            .reader
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+            .read_line(&mut response)
+        {
+            // like in listen_json, the timeout was set by a command on another handle
+            Err(Error(ErrorCode::Timeout)) => continue,
+            result => result?,
+        }
+        return Ok(response.trim_end().to_string());
+    }
 }
 
-fn send_command_sync(instance: &Mpv, command: Value) -> String {
-    let mut stream = clone_LocalSocketStream(&instance.stream);
-    match serde_json::to_writer(&mut stream, &command) {
-        Err(why) => panic!("Error: Could not write to socket: {}", why),
-        Ok(_) => {
-            stream.write_all(b"\n").unwrap();
-            let mut response = String::new();
-            {
-                let mut reader = BufReader::new(stream);
-                while !response.contains("\"error\":") {
-                    response.clear();
-                    reader.read_line(&mut response).unwrap();
+/// Receivers of [EventHandle::observe_property_channel] by observation id.
+pub(crate) type PropertyRoutes = Arc<Mutex<HashMap<isize, Sender<Property>>>>;
+
+/// Sends a property change to the receiver observing it, if there is one, and to the
+/// event receiver otherwise.
+fn route_property_change(
+    instance: &Mpv,
+    routes: &PropertyRoutes,
+    events: &Sender<Result<Event, Error>>,
+    id: usize,
+    property: Property,
+) {
+    let mut routes = routes.lock().unwrap_or_else(PoisonError::into_inner);
+    let key = id as isize;
+    match routes.get(&key) {
+        Some(route) => {
+            if route.send(property).is_err() {
+                // nobody is listening anymore. The reply is skipped like any other
+                // unmatched one, waiting for it here would block the thread.
+                routes.remove(&key);
+                let mut unobserve = [json!({"command": ["unobserve_property", key]})];
+                if write_commands(instance, &mut unobserve).is_ok() {
+                    instance
+                        .observations
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .remove(&key);
                 }
-                core::mem::forget(reader.into_inner());
             }
-            debug!("Response: {}", response.trim_end());
-            response
+        }
+        None => {
+            let _ = events.send(Ok(Event::PropertyChange { id, property }));
         }
     }
 }
 
+/// Reads the socket until it closes, forwarding replies to `replies` and events to `events`.
+/// Used by [Mpv::into_event_thread], which makes commands wait on the other end of `replies`.
+pub(crate) fn run_event_thread(
+    mut instance: Mpv,
+    events: Sender<Result<Event, Error>>,
+    replies: Sender<Value>,
+    routes: PropertyRoutes,
+    stop: Arc<AtomicBool>,
+) {
+    while !stop.load(Ordering::Relaxed) {
+        let mut response = String::new();
+        match instance
+            .reader
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+            .read_line(&mut response)
+        {
+            Ok(()) => {}
+            // a clone of the connection set a read timeout on the shared socket
+            Err(Error(ErrorCode::Timeout)) => continue,
+            Err(why) => {
+                // dropping `replies` wakes up commands still waiting for a reply
+                let _ = events.send(Err(why));
+                return;
+            }
+        }
+        let e = match serde_json::from_str::<Value>(&response) {
+            Ok(e) => e,
+            Err(why) => {
+                // a dropped receiver only means nobody wants the events any more
+                let _ = events.send(Err(Error(ErrorCode::JsonParseError(why.to_string()))));
+                continue;
+            }
+        };
+
+        if e["event"].is_string() {
+            debug!("Event: {}", response.trim_end());
+            match event_from_json(&instance, e) {
+                Ok(Event::PropertyChange { id, property }) => {
+                    route_property_change(&instance, &routes, &events, id, property);
+                }
+                event => {
+                    let _ = events.send(event);
+                }
+            }
+        } else if e.get("request_id").is_some() {
+            if replies.send(e).is_err() {
+                // every handle is gone
+                return;
+            }
+        } else {
+            debug!("Bad response: {:?}", response);
+        }
+    }
+}
+
+/// Buffers the read side of the socket. A line cut off by a timeout is kept, so
+/// the next read continues it instead of returning only its second half.
+pub(crate) struct LineReader {
+    reader: BufReader<LocalSocketStream>,
+    partial: Vec<u8>,
+}
+
+impl LineReader {
+    pub(crate) fn new(reader: BufReader<LocalSocketStream>) -> LineReader {
+        LineReader {
+            reader,
+            partial: Vec::new(),
+        }
+    }
+
+    pub(crate) fn capacity(&self) -> usize {
+        self.reader.capacity()
+    }
+
+    pub(crate) fn get_mut(&mut self) -> &mut BufReader<LocalSocketStream> {
+        &mut self.reader
+    }
+
+    /// Reads the next line, failing with `ErrorCode::Disconnected` once mpv closed the
+    /// socket or the socket stopped working.
+    ///
+    /// mpv passes through whatever bytes e.g. filenames or metadata contain, so
+    /// invalid UTF-8 is replaced instead of failing the line.
+    fn read_line(&mut self, line: &mut String) -> Result<(), Error> {
+        match self.reader.read_until(b'\n', &mut self.partial) {
+            Ok(0) => Err(Error(ErrorCode::Disconnected)),
+            Ok(_) => {
+                line.push_str(&String::from_utf8_lossy(&self.partial));
+                self.partial.clear();
+                Ok(())
+            }
+            Err(why) if is_timeout(&why) => Err(Error(ErrorCode::Timeout)),
+            Err(why) => {
+                debug!("Could not read from socket: {}", why);
+                Err(Error(ErrorCode::Disconnected))
+            }
+        }
+    }
+
+    /// Like [LineReader::read_line], but fails with `ErrorCode::Timeout` once
+    /// `deadline` passed. The read timeout is shared by all handles of the socket, so
+    /// a timeout that ends the read early is retried until our own deadline passed.
+    ///
+    /// The read timeout is only set for a deadline and left as it is afterwards:
+    /// resetting it could clear the timeout another handle relies on. Reads without
+    /// a deadline just retry when it ends them.
+    fn read_line_until(
+        &mut self,
+        line: &mut String,
+        deadline: Option<Instant>,
+    ) -> Result<(), Error> {
+        loop {
+            let remaining = time_left(deadline)?;
+            #[cfg(unix)]
+            if remaining.is_some() {
+                self.set_read_timeout(remaining)?;
+            }
+            #[cfg(not(unix))]
+            let _ = remaining;
+            match self.read_line(line) {
+                Err(Error(ErrorCode::Timeout)) => continue,
+                result => return result,
+            }
+        }
+    }
+
+    /// Like [LineReader::read_line], but returns `false` instead of waiting if no
+    /// complete line has arrived yet. What was read of an incomplete line is kept for
+    /// the next read.
+    ///
+    /// The socket is polled instead of switched to non-blocking mode, as that flag is
+    /// shared with the write side and every clone of the connection.
+    #[cfg(unix)]
+    fn read_ready_line(&mut self, line: &mut String) -> Result<bool, Error> {
+        loop {
+            let buffered = self.reader.buffer();
+            if let Some(end) = buffered.iter().position(|&byte| byte == b'\n') {
+                self.partial.extend_from_slice(&buffered[..=end]);
+                self.reader.consume(end + 1);
+                line.push_str(&String::from_utf8_lossy(&self.partial));
+                self.partial.clear();
+                return Ok(true);
+            }
+            let length = buffered.len();
+            self.partial.extend_from_slice(buffered);
+            self.reader.consume(length);
+
+            if !self.poll_readable()? {
+                return Ok(false);
+            }
+            // the buffer is empty, so this is a single read which returns right away
+            match self.reader.fill_buf() {
+                Ok([]) => return Err(Error(ErrorCode::Disconnected)),
+                Ok(_) => {}
+                Err(why) if is_timeout(&why) => return Ok(false),
+                Err(why) => {
+                    debug!("Could not read from socket: {}", why);
+                    return Err(Error(ErrorCode::Disconnected));
+                }
+            }
+        }
+    }
+
+    /// Returns whether a read would not block, also when the socket was closed.
+    #[cfg(unix)]
+    fn poll_readable(&self) -> Result<bool, Error> {
+        let mut fd = libc::pollfd {
+            fd: self.reader.get_ref().as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        loop {
+            match unsafe { libc::poll(&mut fd, 1, 0) } {
+                -1 => {
+                    let why = std::io::Error::last_os_error();
+                    if why.kind() != std::io::ErrorKind::Interrupted {
+                        return Err(Error(ErrorCode::ConnectError(why.to_string())));
+                    }
+                }
+                ready => return Ok(ready > 0),
+            }
+        }
+    }
+
+    #[cfg(unix)]
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> Result<(), Error> {
+        use std::os::unix::net::UnixStream;
+        // borrow the fd without taking ownership
+        let stream = unsafe { UnixStream::from_raw_fd(self.reader.get_ref().as_raw_fd()) };
+        let result = stream.set_read_timeout(timeout);
+        std::mem::forget(stream);
+        result.map_err(|why| Error(ErrorCode::ConnectError(why.to_string())))
+    }
+}
+
+fn is_timeout(error: &std::io::Error) -> bool {
+    matches!(
+        error.kind(),
+        std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+    )
+}
+
+thread_local! {
+    /// Set by [with_call_timeout] for the duration of a single call.
+    static CALL_TIMEOUT: Cell<Option<Duration>> = const { Cell::new(None) };
+}
+
+/// Runs `f` with `timeout` instead of the connection-wide timeout for commands sent
+/// from this thread.
+pub(crate) fn with_call_timeout<T>(timeout: Duration, f: impl FnOnce() -> T) -> T {
+    struct Restore(Option<Duration>);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            CALL_TIMEOUT.with(|call_timeout| call_timeout.set(self.0));
+        }
+    }
+    let _restore = Restore(CALL_TIMEOUT.with(|call_timeout| call_timeout.replace(Some(timeout))));
+    f()
+}
+
+/// Sends a raw request to mpv and returns the reply object, including its `error` field.
+///
+/// The request is written as a single line terminated by `\n` and flushed immediately.
+pub fn send_mpv_request(instance: &Mpv, command: &[Value]) -> Result<Value, Error> {
+    send_command_sync(instance, json!({ "command": command }))
+}
+
+/// Sends several raw requests at once and returns their reply objects in the same order.
+/// The replies are only awaited after all requests were written, which saves a round
+/// trip per request.
+pub fn send_mpv_requests(instance: &Mpv, commands: &[Vec<Value>]) -> Result<Vec<Value>, Error> {
+    let commands = commands
+        .iter()
+        .map(|command| json!({ "command": command }))
+        .collect();
+    send_commands_sync(instance, commands)
+}
+
+fn send_command_sync(instance: &Mpv, command: Value) -> Result<Value, Error> {
+    let mut replies = send_commands_sync(instance, vec![command])?;
+    Ok(replies.remove(0))
+}
+
+/// Writes all `commands` at once and returns their replies in the same order.
+fn send_commands_sync(instance: &Mpv, mut commands: Vec<Value>) -> Result<Vec<Value>, Error> {
+    if instance.poisoned.load(Ordering::Relaxed) {
+        return Err(Error(ErrorCode::WriteError(
+            "a previous command could not be written completely".to_string(),
+        )));
+    }
+    let timeout = CALL_TIMEOUT.with(Cell::get).or(*instance
+        .timeout
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner));
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+
+    if let Some(ref replies) = instance.replies {
+        // the event thread owns the read side and forwards every reply to us
+        let replies = replies.lock().unwrap_or_else(PoisonError::into_inner);
+        let request_ids = write_commands(instance, &mut commands)?;
+        return collect_replies(&request_ids, deadline, |remaining| match remaining {
+            Some(remaining) => replies.recv_timeout(remaining).map_err(|why| match why {
+                RecvTimeoutError::Timeout => Error(ErrorCode::Timeout),
+                RecvTimeoutError::Disconnected => Error(ErrorCode::Disconnected),
+            }),
+            None => replies.recv().map_err(|_| Error(ErrorCode::Disconnected)),
+        });
+    }
+    // holding the reader while writing keeps other threads from reading our replies
+    let mut reader = instance
+        .reader
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    let request_ids = write_commands(instance, &mut commands)?;
+    // the read timeout is left set afterwards, see LineReader::read_line_until
+    collect_replies(&request_ids, deadline, |_remaining| {
+        let mut response = String::new();
+        reader.read_line_until(&mut response, deadline)?;
+        serde_json::from_str::<Value>(&response)
+            .map_err(|why| Error(ErrorCode::JsonParseError(why.to_string())))
+    })
+}
+
+/// Reads lines with `next` until there is a reply for each of `request_ids`. `next` is
+/// passed the time left until `deadline`.
+fn collect_replies(
+    request_ids: &[u64],
+    deadline: Option<Instant>,
+    mut next: impl FnMut(Option<Duration>) -> Result<Value, Error>,
+) -> Result<Vec<Value>, Error> {
+    let mut replies: Vec<Option<Value>> = vec![None; request_ids.len()];
+    let mut missing = request_ids.len();
+    // events and replies to other requests can arrive in between, skip them
+    while missing > 0 {
+        let reply = next(time_left(deadline)?)?;
+        if reply.get("event").is_some() {
+            // mpv may already be sending events (e.g. right after connecting), these
+            // are never replies, whatever else they contain
+            debug!("Skipped event: {}", reply);
+            continue;
+        }
+        let index = reply["request_id"]
+            .as_u64()
+            .and_then(|id| request_ids.iter().position(|&request_id| request_id == id));
+        match index {
+            Some(index) if replies[index].is_none() => {
+                debug!("Response: {}", reply);
+                replies[index] = Some(reply);
+                missing -= 1;
+            }
+            _ => debug!("Skipped: {}", reply),
+        }
+    }
+    Ok(replies.into_iter().flatten().collect())
+}
+
+/// Returns the time left until `deadline`, failing with `ErrorCode::Timeout` once it passed.
+fn time_left(deadline: Option<Instant>) -> Result<Option<Duration>, Error> {
+    match deadline {
+        Some(deadline) => deadline
+            .checked_duration_since(Instant::now())
+            .filter(|remaining| !remaining.is_zero())
+            .map(Some)
+            .ok_or(Error(ErrorCode::Timeout)),
+        None => Ok(None),
+    }
+}
+
+/// Tags each command with a fresh request_id and writes them with a single write.
+/// Every command is one line of JSON terminated by `\n`, which is how mpv frames them.
+fn write_commands(instance: &Mpv, commands: &mut [Value]) -> Result<Vec<u64>, Error> {
+    let mut request_ids = Vec::with_capacity(commands.len());
+    let mut lines = String::new();
+    for command in commands.iter_mut() {
+        let request_id = instance.next_request_id.fetch_add(1, Ordering::Relaxed);
+        command["request_id"] = json!(request_id);
+        request_ids.push(request_id);
+        lines.push_str(&command.to_string());
+        lines.push('\n');
+    }
+    let mut stream = clone_LocalSocketStream(&instance.stream);
+    let result = write_lines(&mut stream, lines.as_bytes());
+    core::mem::forget(stream);
+    if let Err((written, why)) = result {
+        // mpv may have received part of a line, which would corrupt the next command
+        instance.poisoned.store(true, Ordering::Relaxed);
+        let complete = lines.as_bytes()[..written]
+            .iter()
+            .filter(|&&byte| byte == b'\n')
+            .count();
+        return Err(Error(ErrorCode::WriteError(format!(
+            "{} ({} of {} commands and {} of {} bytes written)",
+            why,
+            complete,
+            commands.len(),
+            written,
+            lines.len()
+        ))));
+    }
+    Ok(request_ids)
+}
+
+/// Writes and flushes `lines` like `write_all`, but on failure also returns how many
+/// bytes were written before.
+fn write_lines(writer: &mut impl Write, lines: &[u8]) -> Result<(), (usize, std::io::Error)> {
+    let mut written = 0;
+    while written < lines.len() {
+        match writer.write(&lines[written..]) {
+            Ok(0) => return Err((written, std::io::ErrorKind::WriteZero.into())),
+            Ok(n) => written += n,
+            Err(why) if why.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(why) => return Err((written, why)),
+        }
+    }
+    // flush right away, relays in between may otherwise hold back the last command
+    writer.flush().map_err(|why| (written, why))
+}
+
 fn json_map_to_hashmap(map: &serde_json::map::Map<String, Value>) -> HashMap<String, MpvDataType> {
     let mut output_map: HashMap<String, MpvDataType> = HashMap::new();
-    for (ref key, ref value) in map.iter() {
-        match **value {
+    for (key, value) in map.iter() {
+        match *value {
             Value::Array(ref array) => {
                 output_map.insert(
                     key.to_string(),
@@ -477,6 +1552,8 @@ fn json_map_to_hashmap(map: &serde_json::map::Map<String, Value>) -> HashMap<Str
                         key.to_string(),
                         MpvDataType::Usize(n.as_u64().unwrap() as usize),
                     );
+                } else if n.is_i64() {
+                    output_map.insert(key.to_string(), MpvDataType::I64(n.as_i64().unwrap()));
                 } else if n.is_f64() {
                     output_map.insert(key.to_string(), MpvDataType::Double(n.as_f64().unwrap()));
                 } else {
@@ -493,16 +1570,16 @@ fn json_map_to_hashmap(map: &serde_json::map::Map<String, Value>) -> HashMap<Str
                 );
             }
             Value::Null => {
-                unimplemented!();
+                output_map.insert(key.to_string(), MpvDataType::Null);
             }
         }
     }
     output_map
 }
 
-fn json_array_to_vec(array: &Vec<Value>) -> Vec<MpvDataType> {
+fn json_array_to_vec(array: &[Value]) -> Vec<MpvDataType> {
     let mut output: Vec<MpvDataType> = Vec::new();
-    if array.len() > 0 {
+    if !array.is_empty() {
         match array[0] {
             Value::Array(_) => {
                 for entry in array {
@@ -525,6 +1602,8 @@ fn json_array_to_vec(array: &Vec<Value>) -> Vec<MpvDataType> {
                     if let Value::Number(ref n) = *entry {
                         if n.is_u64() {
                             output.push(MpvDataType::Usize(n.as_u64().unwrap() as usize));
+                        } else if n.is_i64() {
+                            output.push(MpvDataType::I64(n.as_i64().unwrap()));
                         } else if n.is_f64() {
                             output.push(MpvDataType::Double(n.as_f64().unwrap()));
                         } else {
@@ -551,19 +1630,24 @@ fn json_array_to_vec(array: &Vec<Value>) -> Vec<MpvDataType> {
             }
 
             Value::Null => {
-                unimplemented!();
+                for entry in array {
+                    if let Value::Null = *entry {
+                        output.push(MpvDataType::Null);
+                    }
+                }
             }
         }
     }
     output
 }
 
-fn json_array_to_playlist(array: &Vec<Value>) -> Vec<PlaylistEntry> {
+fn json_array_to_playlist(array: &[Value]) -> Vec<PlaylistEntry> {
     let mut output: Vec<PlaylistEntry> = Vec::new();
     for (id, entry) in array.iter().enumerate() {
         let mut filename: String = String::new();
         let mut title: String = String::new();
         let mut current: bool = false;
+        let mut playlist_entry_id: Option<usize> = None;
         if let Value::String(ref f) = entry["filename"] {
             filename = f.to_string();
         }
@@ -573,12 +1657,286 @@ fn json_array_to_playlist(array: &Vec<Value>) -> Vec<PlaylistEntry> {
         if let Value::Bool(ref b) = entry["current"] {
             current = *b;
         }
+        if let Value::Number(ref n) = entry["id"] {
+            playlist_entry_id = n.as_u64().map(|n| n as usize);
+        }
         output.push(PlaylistEntry {
             id,
             filename,
             title,
             current,
+            playlist_entry_id,
         });
     }
     output
 }
+
+fn json_array_to_editions(array: &[Value]) -> Vec<Edition> {
+    array
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| Edition {
+            id: entry["id"].as_u64().map_or(index, |id| id as usize),
+            title: entry["title"].as_str().map(str::to_string),
+            default: entry["default"].as_bool().unwrap_or(false),
+        })
+        .collect()
+}
+
+/// Entries with a type this crate doesn't know are left out.
+fn json_array_to_tracks(array: &[Value]) -> Vec<Track> {
+    array
+        .iter()
+        .filter_map(|entry| {
+            let track_type = match entry["type"].as_str()? {
+                "audio" => TrackType::Audio,
+                "sub" => TrackType::Sub,
+                "video" => TrackType::Video,
+                _ => return None,
+            };
+            Some(Track {
+                id: entry["id"].as_u64()? as usize,
+                track_type,
+                title: entry["title"].as_str().map(str::to_string),
+                lang: entry["lang"].as_str().map(str::to_string),
+                codec: entry["codec"].as_str().map(str::to_string),
+                default: entry["default"].as_bool().unwrap_or(false),
+                external: entry["external"].as_bool().unwrap_or(false),
+                selected: entry["selected"].as_bool().unwrap_or(false),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn editions_fixture() {
+        // edition-list of a Matroska file with one unnamed edition
+        let reply = json!({
+            "data": [
+                {"id": 0, "default": true, "title": "Theatrical Cut"},
+                {"id": 1, "default": false},
+                {"id": 2, "default": false, "title": "Director's Cut"}
+            ],
+            "request_id": 0,
+            "error": "success"
+        });
+        let editions = Vec::<Edition>::get_value(reply).unwrap();
+        let editions: Vec<(usize, Option<&str>, bool)> = editions
+            .iter()
+            .map(|edition| (edition.id, edition.title.as_deref(), edition.default))
+            .collect();
+        assert_eq!(
+            editions,
+            [
+                (0, Some("Theatrical Cut"), true),
+                (1, None, false),
+                (2, Some("Director's Cut"), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn switches() {
+        for off in ["no", "false", "0"] {
+            assert!(!switch_is_on(off), "{}", off);
+        }
+        for on in ["inf", "yes", "5"] {
+            assert!(switch_is_on(on), "{}", on);
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod socket_tests {
+    use super::*;
+    use crate::socket_tests::{connected_pair, serve};
+    use std::thread;
+
+    /// Accepts `room` bytes, then fails like a socket mpv stopped reading from.
+    struct ShortWriter {
+        room: usize,
+        written: Vec<u8>,
+    }
+
+    impl Write for ShortWriter {
+        fn write(&mut self, bytes: &[u8]) -> std::io::Result<usize> {
+            if self.room == 0 {
+                return Err(std::io::ErrorKind::BrokenPipe.into());
+            }
+            // hand out at most 4 bytes at once to test the retries as well
+            let n = bytes.len().min(self.room).min(4);
+            self.written.extend_from_slice(&bytes[..n]);
+            self.room -= n;
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn write_lines_reports_the_bytes_written() {
+        let lines = b"{\"command\":[\"stop\"]}\n{\"command\":[\"quit\"]}\n";
+        let mut writer = ShortWriter {
+            room: 30,
+            written: Vec::new(),
+        };
+        let (written, why) = write_lines(&mut writer, lines).unwrap_err();
+        assert_eq!(written, 30);
+        assert_eq!(why.kind(), std::io::ErrorKind::BrokenPipe);
+        assert_eq!(writer.written, lines[..30]);
+
+        let mut writer = ShortWriter {
+            room: usize::MAX,
+            written: Vec::new(),
+        };
+        write_lines(&mut writer, lines).unwrap();
+        assert_eq!(writer.written, lines);
+    }
+
+    fn short_read_timeout(mpv: &mut Mpv) {
+        // what a command with a timeout on another handle leaves behind
+        mpv.reader
+            .get_mut()
+            .unwrap()
+            .set_read_timeout(Some(Duration::from_millis(10)))
+            .unwrap();
+    }
+
+    /// The read timeout of the socket, as the kernel rounded it.
+    fn read_timeout(mpv: &mut Mpv) -> Option<Duration> {
+        use std::os::unix::net::UnixStream;
+        let fd = mpv.reader.get_mut().unwrap().reader.get_ref().as_raw_fd();
+        let stream = unsafe { UnixStream::from_raw_fd(fd) };
+        let timeout = stream.read_timeout().unwrap();
+        std::mem::forget(stream);
+        timeout
+    }
+
+    #[test]
+    fn end_file_events_carry_the_reason() {
+        let (mut mpv, mut peer) = connected_pair();
+        peer.write_all(
+            b"{\"event\":\"end-file\",\"reason\":\"error\",\"file_error\":\"loading failed\"}\n\
+            {\"event\":\"end-file\",\"reason\":\"eof\"}\n",
+        )
+        .unwrap();
+        assert!(matches!(
+            mpv.event_listen(),
+            Ok(Event::EndFile { reason: EndFileReason::Error(ref error) }) if error == "loading failed"
+        ));
+        assert!(matches!(
+            mpv.event_listen(),
+            Ok(Event::EndFile {
+                reason: EndFileReason::Eof
+            })
+        ));
+    }
+
+    #[test]
+    fn property_strings_with_null_data_are_missing() {
+        let (mpv, peer) = connected_pair();
+        let server = serve(peer, |request| match request["command"][1].as_str() {
+            Some("pause") => vec![json!({"error": "success", "data": "no"})],
+            // what mpv sends for unknown, unavailable and unconvertible properties
+            _ => vec![json!({"error": "success", "data": null})],
+        });
+        assert_eq!(get_mpv_property_string(&mpv, "pause").unwrap(), "no");
+        for property in ["no-such-property", "chapter-metadata", "vo-passes"] {
+            assert!(matches!(
+                get_mpv_property_string(&mpv, property),
+                Err(Error(ErrorCode::MissingValue))
+            ));
+        }
+        drop(mpv);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn commands_are_newline_terminated_lines_sent_right_away() {
+        let (mpv, mut peer) = connected_pair();
+        let sent = thread::spawn(move || {
+            send_mpv_requests(
+                &mpv,
+                &[vec![json!("stop")], vec![json!("show-text"), json!("a\nb")]],
+            )
+        });
+        // nothing else is written until mpv replies, so both lines must already be there
+        let expected = "{\"command\":[\"stop\"],\"request_id\":1}\n\
+            {\"command\":[\"show-text\",\"a\\nb\"],\"request_id\":2}\n";
+        let mut received = vec![0; expected.len()];
+        peer.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        peer.read_exact(&mut received).unwrap();
+        assert_eq!(String::from_utf8(received).unwrap(), expected);
+
+        peer.write_all(
+            b"{\"request_id\":2,\"error\":\"success\"}\n{\"request_id\":1,\"error\":\"success\"}\n",
+        )
+        .unwrap();
+        let replies = sent.join().unwrap().unwrap();
+        assert_eq!(replies[0]["request_id"], 1);
+        assert_eq!(replies[1]["request_id"], 2);
+    }
+
+    #[test]
+    fn a_short_write_refuses_further_commands() {
+        let (mpv, peer) = connected_pair();
+        // mpv stops reading, so the socket buffer fills up and the write stalls halfway
+        let stream = unsafe { std::os::unix::net::UnixStream::from_raw_fd(mpv.stream.as_raw_fd()) };
+        stream
+            .set_write_timeout(Some(Duration::from_millis(50)))
+            .unwrap();
+        std::mem::forget(stream);
+        let huge = "x".repeat(8 * 1024 * 1024);
+        match set_mpv_property(&mpv, "title", json!(huge)) {
+            Err(Error(ErrorCode::WriteError(message))) => {
+                assert!(message.contains("0 of 1 commands"), "{}", message)
+            }
+            result => panic!("unexpected result {:?}", result),
+        }
+
+        // even once mpv reads again, the half-written line would corrupt the next one
+        let server = serve(peer, |_| vec![json!({"error": "success"})]);
+        match set_mpv_property(&mpv, "pause", json!(true)) {
+            Err(Error(ErrorCode::WriteError(_))) => {}
+            result => panic!("unexpected result {:?}", result),
+        }
+        drop(mpv);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn listen_raw_outlasts_a_read_timeout_set_elsewhere() {
+        let (mut mpv, mut peer) = connected_pair();
+        short_read_timeout(&mut mpv);
+        let writer = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(100));
+            peer.write_all(b"{\"event\":\"idle\"}\n").unwrap();
+            peer
+        });
+        assert_eq!(listen_raw(&mut mpv).unwrap(), "{\"event\":\"idle\"}");
+        writer.join().unwrap();
+    }
+
+    #[test]
+    fn commands_without_deadline_outlast_a_read_timeout_set_elsewhere() {
+        let (mut mpv, peer) = connected_pair();
+        let server = serve(peer, |_| {
+            thread::sleep(Duration::from_millis(100));
+            vec![json!({"error": "success", "data": 1.5})]
+        });
+        short_read_timeout(&mut mpv);
+        let left_behind = read_timeout(&mut mpv);
+        assert!(left_behind.is_some());
+        assert_eq!(get_mpv_property::<f64>(&mpv, "speed").unwrap(), 1.5);
+        // the other handle still relies on its timeout
+        assert_eq!(read_timeout(&mut mpv), left_behind);
+        drop(mpv);
+        server.join().unwrap();
+    }
+}
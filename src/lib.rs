@@ -4,18 +4,34 @@ use ipc::*;
 use serde_json::{json, Value};
 use std::collections::HashMap;
 use std::fmt::{self, Display};
-use std::io::BufReader;
+use std::io::{BufReader, Write};
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicIsize, AtomicU64, Ordering};
+use std::sync::mpsc::{channel, Receiver};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread;
+use std::time::Duration;
 #[cfg(windows)]
 use std::os::windows::io::{AsRawHandle, FromRawHandle};
 #[cfg(unix)]
 use std::os::fd::{AsRawFd, FromRawFd};
 use interprocess::local_socket::LocalSocketStream;
 
+/// Events sent by mpv.
+///
+/// Loading a file produces `StartFile` → `FileLoaded` → `PlaybackRestart`, in that
+/// order. Only `StartFile` carries the `playlist_entry_id` of the entry being
+/// loaded; the `FileLoaded` and `PlaybackRestart` that follow belong to that same entry.
 #[derive(Debug)]
 pub enum Event {
     Shutdown,
-    StartFile,
-    EndFile,
+    /// A playlist entry starts loading.
+    StartFile { playlist_entry_id: Option<usize> },
+    /// A file stopped playing, see [EndFileReason] for why.
+    EndFile { reason: EndFileReason },
+    /// The file announced by the preceding [Event::StartFile] was opened. mpv does
+    /// not include the `playlist_entry_id` in this event.
     FileLoaded,
     TracksChanged,
     TrackSwitched,
@@ -29,9 +45,85 @@ pub enum Event {
     Seek,
     PlaybackRestart,
     PropertyChange { id: usize, property: Property },
+    /// mpv sends no data with this event. To know the new chapter, observe `chapter`
+    /// instead, which is reported as [Property::Chapter].
     ChapterChange,
+    /// A `script-message` sent by mpv or a script. The arguments are unescaped from
+    /// JSON, so special characters arrive the way they were sent.
     ClientMessage { args: Vec<String> },
-    Unimplemented,
+    /// A key bound with `script-binding <client name>/<name>`, e.g. in a section
+    /// defined with [MpvCommand::DefineSection], was pressed or released. mpv sends
+    /// these as `client-message`, they are parsed instead of being returned as
+    /// [Event::ClientMessage].
+    KeyBinding {
+        name: String,
+        state: KeyState,
+        /// The key that triggered the binding, e.g. `ctrl+x`.
+        key_name: Option<String>,
+    },
+    /// A hook registered with [Mpv::hook_add] was triggered. mpv waits until it is
+    /// acknowledged with [Mpv::hook_ack] using `hook_id`.
+    Hook { name: String, hook_id: u64 },
+    /// An event that is not modeled by this crate yet. `name` is the `event`
+    /// field sent by mpv.
+    Unimplemented { name: String },
+}
+
+impl Event {
+    /// Returns the name mpv uses for the event, e.g. `"property-change"`, for logging
+    /// or counting events by kind. [Event::Unimplemented] returns `"unimplemented"`,
+    /// its `name` holds the name mpv sent.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Event::Shutdown => "shutdown",
+            Event::StartFile { .. } => "start-file",
+            Event::EndFile { .. } => "end-file",
+            Event::FileLoaded => "file-loaded",
+            Event::TracksChanged => "tracks-changed",
+            Event::TrackSwitched => "track-switched",
+            Event::Idle => "idle",
+            Event::Pause => "pause",
+            Event::Unpause => "unpause",
+            Event::Tick => "tick",
+            Event::VideoReconfig => "video-reconfig",
+            Event::AudioReconfig => "audio-reconfig",
+            Event::MetadataUpdate => "metadata-update",
+            Event::Seek => "seek",
+            Event::PlaybackRestart => "playback-restart",
+            Event::PropertyChange { .. } => "property-change",
+            Event::ChapterChange => "chapter-change",
+            Event::ClientMessage { .. } | Event::KeyBinding { .. } => "client-message",
+            Event::Hook { .. } => "hook",
+            Event::Unimplemented { .. } => "unimplemented",
+        }
+    }
+}
+
+/// The state of a key in [Event::KeyBinding].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyState {
+    Down,
+    Up,
+    /// The key is held down and repeats.
+    Repeat,
+    /// The key was pressed and released at once, e.g. a mouse wheel step.
+    Press,
+}
+
+/// Why a file stopped playing, the `reason` of mpv's `end-file` event.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EndFileReason {
+    /// The file played to its end.
+    Eof,
+    /// Playback was stopped, e.g. by `stop` or by loading another file.
+    Stop,
+    Quit,
+    /// The file could not be played. Holds the `file_error` mpv reported, e.g.
+    /// `loading failed`.
+    Error(String),
+    /// The file was a playlist or similar and was replaced by its entries.
+    Redirect,
+    Unknown,
 }
 
 #[derive(Debug)]
@@ -41,10 +133,117 @@ pub enum Property {
     PlaybackTime(Option<f64>),
     Duration(Option<f64>),
     Metadata(Option<HashMap<String, MpvDataType>>),
+    Edition(Option<i64>),
+    /// The current chapter index. `-1` before the first chapter starts, `None` if the
+    /// file has no chapters.
+    Chapter(Option<i64>),
+    /// `None` if the A point is not set (`"no"`).
+    AbLoopA(Option<f64>),
+    /// `None` if the B point is not set (`"no"`).
+    AbLoopB(Option<f64>),
+    /// Whether playback is stalled, e.g. because it is paused or waiting for the cache.
+    /// Unlike `Pause`, this is also true while buffering.
+    CoreIdle(bool),
+    PausedForCache(bool),
     Unknown { name: String, data: MpvDataType },
 }
 
+/// Commonly used mpv properties, to catch typos in property names at compile time.
+/// Use [KnownProperty::as_str] or [Mpv::get]/[Mpv::set]; every other property can still
+/// be accessed by name with [Mpv::get_property] and [Mpv::set_property].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KnownProperty {
+    AbLoopA,
+    AbLoopB,
+    Aid,
+    Chapter,
+    Chapters,
+    CoreIdle,
+    Duration,
+    Edition,
+    EofReached,
+    Filename,
+    Fullscreen,
+    IdleActive,
+    LoopFile,
+    LoopPlaylist,
+    MediaTitle,
+    Metadata,
+    Mute,
+    Path,
+    Pause,
+    PausedForCache,
+    PercentPos,
+    PlaybackTime,
+    Playlist,
+    PlaylistCount,
+    PlaylistPos,
+    Sid,
+    Speed,
+    SubVisibility,
+    TimePos,
+    TimeRemaining,
+    Vid,
+    Volume,
+    VolumeMax,
+}
+
+impl KnownProperty {
+    /// Returns the name mpv uses for the property, e.g. `"time-pos"`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            KnownProperty::AbLoopA => "ab-loop-a",
+            KnownProperty::AbLoopB => "ab-loop-b",
+            KnownProperty::Aid => "aid",
+            KnownProperty::Chapter => "chapter",
+            KnownProperty::Chapters => "chapters",
+            KnownProperty::CoreIdle => "core-idle",
+            KnownProperty::Duration => "duration",
+            KnownProperty::Edition => "edition",
+            KnownProperty::EofReached => "eof-reached",
+            KnownProperty::Filename => "filename",
+            KnownProperty::Fullscreen => "fullscreen",
+            KnownProperty::IdleActive => "idle-active",
+            KnownProperty::LoopFile => "loop-file",
+            KnownProperty::LoopPlaylist => "loop-playlist",
+            KnownProperty::MediaTitle => "media-title",
+            KnownProperty::Metadata => "metadata",
+            KnownProperty::Mute => "mute",
+            KnownProperty::Path => "path",
+            KnownProperty::Pause => "pause",
+            KnownProperty::PausedForCache => "paused-for-cache",
+            KnownProperty::PercentPos => "percent-pos",
+            KnownProperty::PlaybackTime => "playback-time",
+            KnownProperty::Playlist => "playlist",
+            KnownProperty::PlaylistCount => "playlist-count",
+            KnownProperty::PlaylistPos => "playlist-pos",
+            KnownProperty::Sid => "sid",
+            KnownProperty::Speed => "speed",
+            KnownProperty::SubVisibility => "sub-visibility",
+            KnownProperty::TimePos => "time-pos",
+            KnownProperty::TimeRemaining => "time-remaining",
+            KnownProperty::Vid => "vid",
+            KnownProperty::Volume => "volume",
+            KnownProperty::VolumeMax => "volume-max",
+        }
+    }
+}
+
 pub enum MpvCommand {
+    /// Creates or replaces the input section `name`. `contents` uses the
+    /// `input.conf` syntax, `flags` is either `default` or `force`.
+    DefineSection {
+        name: String,
+        contents: String,
+        flags: Option<String>,
+    },
+    DisableSection(String),
+    /// Enables the input section `name`. `flags` is a `+`-separated list of
+    /// `allow-hide-cursor`, `allow-vo-dragging` and `exclusive`.
+    EnableSection {
+        name: String,
+        flags: Option<String>,
+    },
     LoadFile {
         file: String,
         option: PlaylistAddOptions,
@@ -67,7 +266,12 @@ pub enum MpvCommand {
     PlaylistRemove(usize),
     PlaylistShuffle,
     Quit,
+    /// Arguments are sent as JSON strings, so quotes, newlines, tabs and any other
+    /// unicode arrive unchanged, e.g. in [Event::ClientMessage] or a Lua script's
+    /// `mp.register_script_message` handler. Only NUL characters are not supported,
+    /// as mpv handles arguments as C strings.
     ScriptMessage(Vec<String>),
+    /// Escapes arguments like [MpvCommand::ScriptMessage].
     ScriptMessageTo {
         target: String,
         args: Vec<String>,
@@ -91,6 +295,8 @@ pub enum MpvDataType {
     Bool(bool),
     Double(f64),
     HashMap(HashMap<String, MpvDataType>),
+    /// A negative integer. Non-negative integers are always [MpvDataType::Usize].
+    I64(i64),
     Null,
     Playlist(Playlist),
     String(String),
@@ -113,6 +319,41 @@ pub enum PlaylistAddTypeOptions {
     Playlist,
 }
 
+/// The kind of a [Track], the `type` field of a `track-list` entry.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TrackType {
+    Audio,
+    Sub,
+    Video,
+}
+
+/// Which track of a type is selected, e.g. the result of [Mpv::cycle_subtitle].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TrackSelection {
+    Off,
+    /// The [Track::id] of the selected track.
+    Track(usize),
+}
+
+/// What the player is doing, derived from several properties by
+/// [Mpv::playback_state].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlaybackState {
+    /// Nothing is loaded (`idle-active`).
+    Idle,
+    /// A file is being opened or a seek is in progress, so no frames are
+    /// presented although playback is not paused.
+    Loading,
+    Playing,
+    /// Paused by the user (`pause`).
+    Paused,
+    /// Paused by mpv until the cache is filled again (`paused-for-cache`).
+    Buffering,
+    /// The end of the file was reached and mpv keeps it open
+    /// (`eof-reached`, only with `--keep-open`).
+    Ended,
+}
+
 pub enum SeekOptions {
     Relative,
     Absolute,
@@ -131,6 +372,21 @@ pub enum ErrorCode {
     MpvError(String),
     JsonParseError(String),
     ConnectError(String),
+    /// mpv closed the connection, e.g. because it quit.
+    Disconnected,
+    /// mpv did not reply within the timeout set with [Mpv::set_timeout] or passed to a
+    /// `*_timeout` method.
+    Timeout,
+    /// A command could not be written to the socket completely, so mpv did not
+    /// receive it, or only part of it. Commands sent together with it in one batch,
+    /// e.g. by [Mpv::load_files], are written as one buffer: the ones before the
+    /// failed command may have been received and run. The message says how many
+    /// commands and bytes were written. Once this happened, the connection refuses
+    /// further commands with this error.
+    WriteError(String),
+    /// A writer or file passed in by the caller failed, e.g. the one given to
+    /// [Mpv::save_playlist_m3u]. The connection to mpv is not affected.
+    IoError(String),
     JsonContainsUnexptectedType,
     UnexpectedResult,
     UnexpectedValue,
@@ -139,24 +395,389 @@ pub enum ErrorCode {
     ValueDoesNotContainBool,
     ValueDoesNotContainF64,
     ValueDoesNotContainHashMap,
+    ValueDoesNotContainI64,
     ValueDoesNotContainPlaylist,
     ValueDoesNotContainString,
     ValueDoesNotContainUsize,
+    ValueDoesNotContainVec,
 }
 
+/// The capacity of the read buffer used when none is set with [MpvBuilder::buffer_capacity].
+pub const DEFAULT_BUFFER_CAPACITY: usize = 8 * 1024;
+
+/// How long [MpvBuilder::handshake] waits for mpv to reply.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// The socket [Mpv::connect_default] uses unless `MPV_SOCKET` is set.
+#[cfg(not(windows))]
+pub const DEFAULT_SOCKET: &str = "/tmp/mpvsocket";
+/// The socket [Mpv::connect_default] uses unless `MPV_SOCKET` is set.
+#[cfg(windows)]
+pub const DEFAULT_SOCKET: &str = r"\\.\pipe\mpvsocket";
+
 pub struct Mpv {
     stream: LocalSocketStream,
-    reader: BufReader<LocalSocketStream>,
+    reader: Mutex<LineReader>,
     name: String,
+    /// Properties observed on this connection by id, shared between clones.
+    observations: Arc<Mutex<HashMap<isize, Observation>>>,
+    next_observe_id: Arc<AtomicIsize>,
+    /// Hook names by the id passed to `hook-add`, shared between clones.
+    hooks: Arc<Mutex<HashMap<u64, String>>>,
+    next_hook_id: Arc<AtomicU64>,
+    next_request_id: Arc<AtomicU64>,
+    /// Set once a command could not be written completely. The socket is then out of
+    /// sync with mpv and refuses further commands.
+    poisoned: Arc<AtomicBool>,
+    /// Replies forwarded by the thread started with [Mpv::into_event_thread], which
+    /// then owns the read side of the socket.
+    replies: Option<Arc<Mutex<Receiver<Value>>>>,
+    timeout: Mutex<Option<Duration>>,
+    /// Shared by all clones, shuts the connection down once the last one is gone.
+    shutdown: Arc<ShutdownOnDrop>,
+}
+
+/// Flushes and shuts down the connection when dropped, or before that with
+/// [ShutdownOnDrop::shut_down]. Holds its own handle of the socket, as the ones of
+/// the [Mpv] sharing it may already be closed by then.
+struct ShutdownOnDrop {
+    stream: Mutex<Option<LocalSocketStream>>,
+}
+
+/// The command side of a connection whose events are read by a background thread,
+/// see [Mpv::into_event_thread]. All [Mpv] methods taking `&self` are available
+/// through `Deref`.
+///
+/// Dropping the handle stops the thread. On unix the socket is shut down, which also
+/// ends the thread while it is waiting for mpv. On Windows the thread only notices
+/// after the next line from mpv.
+pub struct EventHandle {
+    mpv: Mpv,
+    stop: Arc<AtomicBool>,
+    routes: PropertyRoutes,
+}
+
+/// Cancels blocking reads of a connection from another thread, see
+/// [Mpv::shutdown_handle].
+pub struct ShutdownHandle {
+    stream: LocalSocketStream,
+}
+
+/// Calls registered callbacks for the events of a connection, for programs that
+/// poll for events themselves, e.g. once per frame of a GUI, instead of blocking
+/// on [Mpv::event_listen] or starting [Mpv::into_event_thread].
+///
+/// # Example
+/// ```no_run
+/// use mpvipc::{Error, EventDispatcher, Mpv};
+/// fn main() -> Result<(), Error> {
+///     let mut mpv = Mpv::connect("/tmp/mpvsocket")?;
+///     mpv.observe_property(1, "volume")?;
+///     let mut dispatcher = EventDispatcher::new();
+///     dispatcher
+///         .on_pause(|| println!("paused"))
+///         .on_property(1, |property| println!("{:?}", property));
+///     loop {
+///         dispatcher.dispatch_pending(&mut mpv)?;
+///         // draw the frame ...
+///     }
+/// }
+/// ```
+#[derive(Default)]
+pub struct EventDispatcher<'a> {
+    handlers: Vec<EventHandler<'a>>,
+}
+
+type EventHandler<'a> = Box<dyn FnMut(&Event) + 'a>;
+
+/// Configures a connection before it is established.
+///
+/// Connections always use a unix socket or a Windows named pipe, so there is no
+/// keepalive to configure. Use [Mpv::ping] to check that mpv is still there while
+/// no other requests are sent.
+///
+/// # Example
+/// ```no_run
+/// use mpvipc::{Error, MpvBuilder};
+/// fn main() -> Result<(), Error> {
+///     let mpv = MpvBuilder::new("/tmp/mpvsocket")
+///         .buffer_capacity(64 * 1024)
+///         .connect()?;
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct MpvBuilder {
+    socket: String,
+    buffer_capacity: usize,
+    handshake: bool,
 }
 #[derive(Debug)]
 pub struct Playlist(pub Vec<PlaylistEntry>);
+/// An entry of the `edition-list` property.
+#[derive(Debug)]
+pub struct Edition {
+    pub id: usize,
+    /// Not every container names its editions.
+    pub title: Option<String>,
+    pub default: bool,
+}
+/// An entry of the `track-list` property.
+#[derive(Debug)]
+pub struct Track {
+    /// The id of the track among the tracks of the same type, as used by `aid`,
+    /// `sid` and `vid`.
+    pub id: usize,
+    pub track_type: TrackType,
+    pub title: Option<String>,
+    /// The language tag, e.g. `eng`.
+    pub lang: Option<String>,
+    pub codec: Option<String>,
+    pub default: bool,
+    /// Whether the track was loaded from a separate file, e.g. with `sub-add`.
+    pub external: bool,
+    pub selected: bool,
+}
+/// A playlist entry prepared for saving the playlist, see [Mpv::export_playlist].
+#[derive(Debug)]
+pub struct ExportEntry {
+    /// The filename as it was added, e.g. a relative path or a URL.
+    pub filename: String,
+    /// The absolute path of the file, `None` for URLs.
+    pub resolved: Option<PathBuf>,
+    pub title: Option<String>,
+    pub current: bool,
+}
+/// What is currently playing, see [Mpv::now_playing].
+/// All fields are `None` while nothing is loaded.
+#[derive(Debug)]
+pub struct NowPlaying {
+    pub filename: Option<String>,
+    pub path: Option<String>,
+    /// The `media-title`, which falls back to the filename if the file has no title.
+    pub title: Option<String>,
+    pub duration: Option<f64>,
+}
+/// The common fields of `video-params`, see [Mpv::video_params].
+/// Fields mpv did not report are `None`, all of them for audio-only files.
+#[derive(Debug, Default)]
+pub struct VideoParams {
+    pub width: Option<usize>,
+    pub height: Option<usize>,
+    pub pixelformat: Option<String>,
+    /// The display aspect ratio.
+    pub aspect: Option<f64>,
+    pub rotate: Option<usize>,
+    /// The frame rate the container reports (`container-fps`).
+    pub fps: Option<f64>,
+}
+
+/// The common fields of `audio-params`, see [Mpv::audio_params].
+/// Fields mpv did not report are `None`, all of them for files without audio.
+#[derive(Debug, Default)]
+pub struct AudioParams {
+    pub samplerate: Option<usize>,
+    pub channel_count: Option<usize>,
+    /// The channel layout, e.g. `stereo` or `5.1`.
+    pub channels: Option<String>,
+    pub format: Option<String>,
+}
+
+/// The size of the OSD (`osd-dimensions`), see [Mpv::osd_dimensions].
+/// Fields mpv did not report are `None`.
+#[derive(Debug, Default)]
+pub struct OsdDimensions {
+    pub width: Option<usize>,
+    pub height: Option<usize>,
+    /// The pixel aspect ratio of the OSD.
+    pub par: Option<f64>,
+    /// The display aspect ratio of the OSD.
+    pub aspect: Option<f64>,
+    /// The margins around the video, e.g. black bars, in OSD pixels.
+    pub margin_top: Option<usize>,
+    pub margin_bottom: Option<usize>,
+    pub margin_left: Option<usize>,
+    pub margin_right: Option<usize>,
+}
+
+/// The state of the demuxer cache, see [Mpv::get_cache_state].
+#[derive(Debug, Default)]
+pub struct CacheState {
+    /// Bytes buffered ahead of the playback position.
+    pub fw_bytes: u64,
+    /// Seconds buffered ahead of the playback position, `None` if unknown.
+    pub cache_duration: Option<f64>,
+    /// Whether playback ran out of cached data.
+    pub underrun: bool,
+    /// The `(start, end)` times in seconds that can be seeked to without
+    /// reading from the source again.
+    pub seekable_ranges: Vec<(f64, f64)>,
+}
+
 #[derive(Debug)]
 pub struct Error(pub ErrorCode);
 
-impl Drop for Mpv {
+impl ShutdownOnDrop {
+    /// Shuts the connection down, unless that already happened.
+    fn shut_down(&self) -> Result<(), Error> {
+        let stream = self
+            .stream
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+        let Some(mut stream) = stream else {
+            return Ok(());
+        };
+        stream
+            .flush()
+            .map_err(|why| Error(ErrorCode::ConnectError(why.to_string())))?;
+        #[cfg(unix)]
+        shutdown_stream(&stream, std::net::Shutdown::Both)
+            .map_err(|why| Error(ErrorCode::ConnectError(why.to_string())))?;
+        Ok(())
+    }
+}
+
+impl Drop for ShutdownOnDrop {
+    fn drop(&mut self) {
+        let _ = self.shut_down();
+    }
+}
+
+/// Iterator over the events of a connection, see [Mpv::events].
+///
+/// Each item is what [Mpv::event_listen] would return. The iterator ends once mpv
+/// closes the connection.
+pub struct Events<'a> {
+    mpv: &'a mut Mpv,
+}
+
+impl Iterator for Events<'_> {
+    type Item = Result<Event, Error>;
+
+    fn next(&mut self) -> Option<Result<Event, Error>> {
+        match listen(self.mpv) {
+            Err(Error(ErrorCode::Disconnected)) => None,
+            result => Some(result),
+        }
+    }
+}
+
+impl<'a> Events<'a> {
+    /// Only yields [Event::PropertyChange] events, as `(id, property)`.
+    /// All other events are dropped, errors are passed through.
+    pub fn property_changes(self) -> impl Iterator<Item = Result<(usize, Property), Error>> + 'a {
+        self.filter_map(|event| match event {
+            Ok(Event::PropertyChange { id, property }) => Some(Ok((id, property))),
+            Ok(_) => None,
+            Err(error) => Some(Err(error)),
+        })
+    }
+
+    /// Only yields the events `predicate` returns true for, e.g.
+    /// `of_kind(|event| matches!(event, Event::EndFile { .. }))`.
+    /// All other events are dropped, errors are passed through.
+    pub fn of_kind<P>(self, mut predicate: P) -> impl Iterator<Item = Result<Event, Error>> + 'a
+    where
+        P: FnMut(&Event) -> bool + 'a,
+    {
+        self.filter(move |event| match event {
+            Ok(event) => predicate(event),
+            Err(_) => true,
+        })
+    }
+}
+
+impl EventHandle {
+    /// # Description
+    ///
+    /// Observes _<property>_ and returns a receiver for its values, decoded like the
+    /// `property` of an [Event::PropertyChange]. Changes of this observation are only
+    /// sent to the returned receiver, not to the event receiver.
+    ///
+    /// The first value is synthetic: it is read with `get_property` before observing,
+    /// so it is available right away, and mpv's own first notification may repeat it.
+    /// Dropping the receiver ends the observation with the next change.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let (mpv, _events) = Mpv::connect("/tmp/mpvsocket")?.into_event_thread()?;
+    /// let pause = mpv.observe_property_channel("pause")?;
+    /// for property in pause {
+    ///     println!("{:?}", property);
+    /// }
+    /// ```
+    pub fn observe_property_channel(&self, property: &str) -> Result<Receiver<Property>, Error> {
+        let (sender, receiver) = channel();
+        let current = match get_mpv_property::<Value>(&self.mpv, property) {
+            Ok(value) => value,
+            Err(Error(ErrorCode::MpvError(ref error))) if error == "property unavailable" => {
+                Value::Null
+            }
+            Err(error) => return Err(error),
+        };
+        // receivers only go away after this function returned
+        let _ = sender.send(property_from_json(property, &current)?);
+
+        let id = self.mpv.next_observe_id();
+        self.routes
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(id, sender);
+        if let Err(error) = observe_mpv_property(&self.mpv, &id, property) {
+            self.routes
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .remove(&id);
+            return Err(error);
+        }
+        Ok(receiver)
+    }
+}
+
+impl Deref for EventHandle {
+    type Target = Mpv;
+
+    fn deref(&self) -> &Mpv {
+        &self.mpv
+    }
+}
+
+impl Drop for EventHandle {
     fn drop(&mut self) {
-        self.disconnect();
+        self.stop.store(true, Ordering::Relaxed);
+        #[cfg(unix)]
+        let _ = shutdown_stream(&self.mpv.stream, std::net::Shutdown::Both);
+    }
+}
+
+impl ShutdownHandle {
+    /// Shuts down the read half of the socket. Blocked and future reads on every
+    /// handle of the connection return `ErrorCode::Disconnected` right away.
+    ///
+    /// Returns `ErrorCode::UnsupportedType` on Windows, as named pipes cannot be
+    /// shut down while another thread reads from them.
+    pub fn signal(&self) -> Result<(), Error> {
+        #[cfg(unix)]
+        return shutdown_stream(&self.stream, std::net::Shutdown::Read)
+            .map_err(|why| Error(ErrorCode::ConnectError(why.to_string())));
+        #[cfg(not(unix))]
+        Err(Error(ErrorCode::UnsupportedType))
+    }
+}
+
+impl fmt::Debug for ShutdownHandle {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("ShutdownHandle").finish_non_exhaustive()
+    }
+}
+
+impl fmt::Debug for EventHandle {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_tuple("EventHandle")
+            .field(&self.mpv.name)
+            .finish()
     }
 }
 
@@ -174,26 +795,121 @@ pub fn clone_LocalSocketStream(stream: &LocalSocketStream) -> LocalSocketStream
     unsafe { LocalSocketStream::from_raw_fd(stream.as_raw_fd()) }
 }
 
+#[cfg(unix)]
+fn shutdown_stream(stream: &LocalSocketStream, how: std::net::Shutdown) -> std::io::Result<()> {
+    use std::os::unix::net::UnixStream;
+    // borrow the fd without taking ownership
+    let socket = unsafe { UnixStream::from_raw_fd(stream.as_raw_fd()) };
+    let result = socket.shutdown(how);
+    std::mem::forget(socket);
+    result
+}
+
+/// interprocess always connects to `\\.\pipe\<name>` on Windows, so a full pipe path
+/// has to be cut down to the name first.
+fn local_socket_name(socket: &str) -> &str {
+    #[cfg(windows)]
+    {
+        const PIPE_PREFIX: &str = r"\\.\pipe\";
+        if let Some(prefix) = socket.get(..PIPE_PREFIX.len()) {
+            if prefix.eq_ignore_ascii_case(PIPE_PREFIX) {
+                return &socket[PIPE_PREFIX.len()..];
+            }
+        }
+    }
+    socket
+}
+
+/// Rejects `NaN` and infinite values, which mpv would otherwise ignore without an error.
+fn finite(value: f64) -> Result<f64, Error> {
+    if value.is_finite() {
+        Ok(value)
+    } else {
+        Err(Error(ErrorCode::UnexpectedValue))
+    }
+}
+
+/// Decodes the `{start, end}` objects of `seekable-ranges`.
+fn seekable_ranges(ranges: &Value) -> Vec<(f64, f64)> {
+    match ranges {
+        Value::Array(ranges) => ranges
+            .iter()
+            .filter_map(|range| Some((range["start"].as_f64()?, range["end"].as_f64()?)))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Converts a metadata object to strings, see [Mpv::chapter_metadata]. Anything but
+/// an object is an empty map.
+fn metadata_strings(metadata: Value) -> HashMap<String, String> {
+    match metadata {
+        Value::Object(map) => map
+            .into_iter()
+            .map(|(key, value)| match value {
+                Value::String(value) => (key, value),
+                value => (key, value.to_string()),
+            })
+            .collect(),
+        _ => HashMap::new(),
+    }
+}
+
+/// Resolves a playlist `filename` against mpv's `working-directory` the way mpv
+/// opens it. URLs (anything with a protocol like `https://`) are not paths.
+fn resolve_filename(filename: &str, working_directory: Option<&Path>) -> Option<PathBuf> {
+    if filename.contains("://") {
+        return None;
+    }
+    let path = Path::new(filename);
+    if path.is_absolute() {
+        Some(path.to_path_buf())
+    } else {
+        working_directory.map(|directory| directory.join(path))
+    }
+}
+
+/// Formats `seconds` as `H:MM:SS`, or `M:SS` below an hour. Fractions are cut off.
+fn format_time(seconds: f64) -> String {
+    let total = seconds.max(0f64) as u64;
+    let (hours, minutes, seconds) = (total / 3600, total / 60 % 60, total % 60);
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{}:{:02}", minutes, seconds)
+    }
+}
+
+/// Unlike [clone_LocalSocketStream], the returned stream owns a duplicated fd/handle
+/// and can be dropped independently of `stream`.
+fn duplicate_stream(stream: &LocalSocketStream) -> std::io::Result<LocalSocketStream> {
+    #[cfg(windows)]
+    {
+        use std::os::windows::io::{BorrowedHandle, IntoRawHandle};
+        let handle = unsafe { BorrowedHandle::borrow_raw(stream.as_raw_handle()) };
+        let owned = handle.try_clone_to_owned()?;
+        Ok(unsafe { LocalSocketStream::from_raw_handle(owned.into_raw_handle()) })
+    }
+    #[cfg(unix)]
+    {
+        use std::os::fd::{BorrowedFd, IntoRawFd};
+        let fd = unsafe { BorrowedFd::borrow_raw(stream.as_raw_fd()) };
+        let owned = fd.try_clone_to_owned()?;
+        Ok(unsafe { LocalSocketStream::from_raw_fd(owned.into_raw_fd()) })
+    }
+}
+
 // unsafe
 impl Clone for Mpv {
+    /// Panics if the socket can't be duplicated, e.g. because the process ran out of
+    /// file descriptors. Use [Mpv::try_clone] to handle that as an error.
     fn clone(&self) -> Self {
-        let stream = clone_LocalSocketStream(&self.stream);
-        let cloned_stream = clone_LocalSocketStream(&self.stream);
-        Mpv {
-            stream,
-            reader: BufReader::new(cloned_stream),
-            name: self.name.clone(),
-        }
+        self.try_clone()
+            .expect("could not duplicate the mpv socket")
     }
 
     fn clone_from(&mut self, source: &Self) {
-        let stream = clone_LocalSocketStream(&self.stream);
-        let cloned_stream = clone_LocalSocketStream(&self.stream);
-        *self = Mpv {
-            stream,
-            reader: BufReader::new(cloned_stream),
-            name: source.name.clone(),
-        }
+        *self = source.clone();
     }
 }
 
@@ -211,6 +927,10 @@ impl Display for ErrorCode {
             ErrorCode::ConnectError(ref msg) => f.write_str(&format!("ConnectError: {}", msg)),
             ErrorCode::JsonParseError(ref msg) => f.write_str(&format!("JsonParseError: {}", msg)),
             ErrorCode::MpvError(ref msg) => f.write_str(&format!("MpvError: {}", msg)),
+            ErrorCode::WriteError(ref msg) => f.write_str(&format!("WriteError: {}", msg)),
+            ErrorCode::IoError(ref msg) => f.write_str(&format!("IoError: {}", msg)),
+            ErrorCode::Disconnected => f.write_str("The connection to mpv was closed"),
+            ErrorCode::Timeout => f.write_str("Timed out waiting for a reply from mpv"),
             ErrorCode::JsonContainsUnexptectedType => {
                 f.write_str("Mpv sent a value with an unexpected type")
             }
@@ -227,6 +947,9 @@ impl Display for ErrorCode {
             ErrorCode::ValueDoesNotContainHashMap => {
                 f.write_str("The received value is not of type \'std::collections::HashMap\'")
             }
+            ErrorCode::ValueDoesNotContainI64 => {
+                f.write_str("The received value is not of type \'std::i64\'")
+            }
             ErrorCode::ValueDoesNotContainPlaylist => {
                 f.write_str("The received value is not of type \'mpvipc::Playlist\'")
             }
@@ -236,6 +959,9 @@ impl Display for ErrorCode {
             ErrorCode::ValueDoesNotContainUsize => {
                 f.write_str("The received value is not of type \'std::usize\'")
             }
+            ErrorCode::ValueDoesNotContainVec => {
+                f.write_str("The received value is not of type \'std::vec::Vec\'")
+            }
         }
     }
 }
@@ -268,12 +994,36 @@ impl GetPropertyTypeHandler for usize {
     }
 }
 
+impl GetPropertyTypeHandler for i64 {
+    fn get_property_generic(instance: &Mpv, property: &str) -> Result<i64, Error> {
+        get_mpv_property::<i64>(instance, property)
+    }
+}
+
 impl GetPropertyTypeHandler for Vec<PlaylistEntry> {
     fn get_property_generic(instance: &Mpv, property: &str) -> Result<Vec<PlaylistEntry>, Error> {
         get_mpv_property::<Vec<PlaylistEntry>>(instance, property)
     }
 }
 
+impl GetPropertyTypeHandler for Vec<String> {
+    fn get_property_generic(instance: &Mpv, property: &str) -> Result<Vec<String>, Error> {
+        get_mpv_property::<Vec<String>>(instance, property)
+    }
+}
+
+impl GetPropertyTypeHandler for Vec<Track> {
+    fn get_property_generic(instance: &Mpv, property: &str) -> Result<Vec<Track>, Error> {
+        get_mpv_property::<Vec<Track>>(instance, property)
+    }
+}
+
+impl GetPropertyTypeHandler for Vec<Edition> {
+    fn get_property_generic(instance: &Mpv, property: &str) -> Result<Vec<Edition>, Error> {
+        get_mpv_property::<Vec<Edition>>(instance, property)
+    }
+}
+
 impl GetPropertyTypeHandler for HashMap<String, MpvDataType> {
     fn get_property_generic(
         instance: &Mpv,
@@ -287,120 +1037,1187 @@ pub trait SetPropertyTypeHandler<T> {
     fn set_property_generic(instance: &Mpv, property: &str, value: T) -> Result<(), Error>;
 }
 
+/// Converts a value of one of the [SetPropertyTypeHandler] types to the JSON sent to
+/// mpv. Fails with `ErrorCode::UnexpectedValue` for `NaN` and infinite numbers, which
+/// JSON can't represent.
+trait IntoPropertyValue {
+    fn into_property_value(self) -> Result<Value, Error>;
+}
+
+impl IntoPropertyValue for bool {
+    fn into_property_value(self) -> Result<Value, Error> {
+        Ok(json!(self))
+    }
+}
+
+impl IntoPropertyValue for String {
+    fn into_property_value(self) -> Result<Value, Error> {
+        Ok(json!(self))
+    }
+}
+
+impl IntoPropertyValue for f64 {
+    fn into_property_value(self) -> Result<Value, Error> {
+        finite(self).map(|value| json!(value))
+    }
+}
+
+impl IntoPropertyValue for usize {
+    fn into_property_value(self) -> Result<Value, Error> {
+        Ok(json!(self))
+    }
+}
+
+impl IntoPropertyValue for &str {
+    fn into_property_value(self) -> Result<Value, Error> {
+        Ok(json!(self))
+    }
+}
+
+impl IntoPropertyValue for i32 {
+    fn into_property_value(self) -> Result<Value, Error> {
+        Ok(json!(self))
+    }
+}
+
+impl IntoPropertyValue for i64 {
+    fn into_property_value(self) -> Result<Value, Error> {
+        Ok(json!(self))
+    }
+}
+
+impl IntoPropertyValue for u32 {
+    fn into_property_value(self) -> Result<Value, Error> {
+        Ok(json!(self))
+    }
+}
+
+impl IntoPropertyValue for f32 {
+    // go through the shortest decimal form, widening to f64 directly would turn
+    // e.g. 0.1 into 0.10000000149011612
+    fn into_property_value(self) -> Result<Value, Error> {
+        finite(f64::from(self))?;
+        match self.to_string().parse::<f64>() {
+            Ok(value) => Ok(json!(value)),
+            Err(_) => Ok(json!(self)),
+        }
+    }
+}
+
 impl SetPropertyTypeHandler<bool> for bool {
     fn set_property_generic(instance: &Mpv, property: &str, value: bool) -> Result<(), Error> {
-        set_mpv_property(instance, property, json!(value))
+        set_mpv_property(instance, property, value.into_property_value()?)
     }
 }
 
 impl SetPropertyTypeHandler<String> for String {
     fn set_property_generic(instance: &Mpv, property: &str, value: String) -> Result<(), Error> {
-        set_mpv_property(instance, property, json!(value))
+        set_mpv_property(instance, property, value.into_property_value()?)
     }
 }
 
 impl SetPropertyTypeHandler<f64> for f64 {
     fn set_property_generic(instance: &Mpv, property: &str, value: f64) -> Result<(), Error> {
-        set_mpv_property(instance, property, json!(value))
+        set_mpv_property(instance, property, value.into_property_value()?)
     }
 }
 
 impl SetPropertyTypeHandler<usize> for usize {
     fn set_property_generic(instance: &Mpv, property: &str, value: usize) -> Result<(), Error> {
-        set_mpv_property(instance, property, json!(value))
+        set_mpv_property(instance, property, value.into_property_value()?)
     }
 }
 
-impl Mpv {
-    pub fn connect(socket: &str) -> Result<Mpv, Error> {
-        match LocalSocketStream::connect(socket) {
-            Ok(stream) => {
-                let cloned_stream = clone_LocalSocketStream(&stream);
-                return Ok(Mpv {
-                    stream,
-                    reader: BufReader::new(cloned_stream),
-                    name: String::from(socket),
-                });
-            }
-            Err(internal_error) => Err(Error(ErrorCode::ConnectError(internal_error.to_string()))),
-        }
+impl<'a> SetPropertyTypeHandler<&'a str> for &'a str {
+    fn set_property_generic(instance: &Mpv, property: &str, value: &str) -> Result<(), Error> {
+        set_mpv_property(instance, property, value.into_property_value()?)
     }
+}
 
-    pub fn disconnect(&self) {
-        /*
-        let mut stream = &self.stream;
-        stream
-            .shutdown(std::net::Shutdown::Both)
-            .expect("socket disconnect");
-        let mut buffer = [0; 32];
-        for _ in 0..stream.bytes().count() {
-            stream.read(&mut buffer[..]).unwrap();
-        }
-        */
+impl SetPropertyTypeHandler<i32> for i32 {
+    fn set_property_generic(instance: &Mpv, property: &str, value: i32) -> Result<(), Error> {
+        set_mpv_property(instance, property, value.into_property_value()?)
     }
+}
 
-    pub fn get_stream_ref(&self) -> &LocalSocketStream {
-        &self.stream
+impl SetPropertyTypeHandler<i64> for i64 {
+    fn set_property_generic(instance: &Mpv, property: &str, value: i64) -> Result<(), Error> {
+        set_mpv_property(instance, property, value.into_property_value()?)
     }
+}
 
-    pub fn get_metadata(&self) -> Result<HashMap<String, MpvDataType>, Error> {
-        match get_mpv_property(self, "metadata") {
-            Ok(map) => Ok(map),
-            Err(err) => Err(err),
-        }
+impl SetPropertyTypeHandler<u32> for u32 {
+    fn set_property_generic(instance: &Mpv, property: &str, value: u32) -> Result<(), Error> {
+        set_mpv_property(instance, property, value.into_property_value()?)
     }
+}
 
-    pub fn get_playlist(&self) -> Result<Playlist, Error> {
-        match get_mpv_property::<Vec<PlaylistEntry>>(self, "playlist") {
-            Ok(entries) => Ok(Playlist(entries)),
-            Err(msg) => Err(msg),
-        }
+impl SetPropertyTypeHandler<f32> for f32 {
+    fn set_property_generic(instance: &Mpv, property: &str, value: f32) -> Result<(), Error> {
+        set_mpv_property(instance, property, value.into_property_value()?)
     }
+}
 
+impl MpvDataType {
     /// # Description
     ///
-    /// Retrieves the property value from mpv.
-    ///
-    /// ## Supported types
-    /// - String
-    /// - bool
-    /// - HashMap<String, String> (e.g. for the 'metadata' property)
-    /// - Vec<PlaylistEntry> (for the 'playlist' property)
-    /// - usize
-    /// - f64
-    ///
-    /// ## Input arguments
-    ///
-    /// - **property** defines the mpv property that should be retrieved
+    /// Returns the map of a [MpvDataType::HashMap], or
+    /// `ErrorCode::ValueDoesNotContainHashMap` for any other value.
     ///
     /// # Example
     /// ```
-    /// use mpvipc::{Mpv, Error};
-    /// fn main() -> Result<(), Error> {
-    ///     let mpv = Mpv::connect("/tmp/mpvsocket")?;
-    ///     let paused: bool = mpv.get_property("pause")?;
-    ///     let title: String = mpv.get_property("media-title")?;
-    ///     Ok(())
-    /// }
+    /// # use mpvipc::{Error, MpvDataType};
+    /// # use std::collections::HashMap;
+    /// # fn main() -> Result<(), Error> {
+    /// let mut metadata = HashMap::new();
+    /// metadata.insert("title".to_string(), MpvDataType::String("Intro".to_string()));
+    /// let mut map = MpvDataType::HashMap(metadata).try_into_map()?;
+    /// let title = map.remove("title").unwrap().try_into_string()?;
+    /// assert_eq!(title, "Intro");
+    /// # Ok(())
+    /// # }
     /// ```
-    pub fn get_property<T: GetPropertyTypeHandler>(&self, property: &str) -> Result<T, Error> {
-        T::get_property_generic(self, property)
+    pub fn try_into_map(self) -> Result<HashMap<String, MpvDataType>, Error> {
+        HashMap::try_from(self)
     }
 
-    /// # Description
-    ///
-    /// Retrieves the property value from mpv.
-    /// The result is always of type String, regardless of the type of the value of the mpv property
-    ///
-    /// ## Input arguments
-    ///
-    /// - **property** defines the mpv property that should be retrieved
-    ///
-    /// # Example
-    ///
-    /// ```
-    /// use mpvipc::{Mpv, Error};
-    /// fn main() -> Result<(), Error> {
+    /// Returns the elements of a [MpvDataType::Array], or
+    /// `ErrorCode::ValueDoesNotContainVec` for any other value.
+    pub fn try_into_vec(self) -> Result<Vec<MpvDataType>, Error> {
+        Vec::try_from(self)
+    }
+
+    /// Returns the string of a [MpvDataType::String], or
+    /// `ErrorCode::ValueDoesNotContainString` for any other value.
+    pub fn try_into_string(self) -> Result<String, Error> {
+        String::try_from(self)
+    }
+
+    /// Returns the name of the JSON type the value was decoded from: `"array"`,
+    /// `"boolean"`, `"number"`, `"object"`, `"string"` or `"null"`.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            MpvDataType::Array(_) | MpvDataType::Playlist(_) => "array",
+            MpvDataType::Bool(_) => "boolean",
+            MpvDataType::Double(_) | MpvDataType::I64(_) | MpvDataType::Usize(_) => "number",
+            MpvDataType::HashMap(_) => "object",
+            MpvDataType::Null => "null",
+            MpvDataType::String(_) => "string",
+        }
+    }
+}
+
+impl TryFrom<MpvDataType> for HashMap<String, MpvDataType> {
+    type Error = Error;
+
+    fn try_from(value: MpvDataType) -> Result<HashMap<String, MpvDataType>, Error> {
+        match value {
+            MpvDataType::HashMap(map) => Ok(map),
+            _ => Err(Error(ErrorCode::ValueDoesNotContainHashMap)),
+        }
+    }
+}
+
+impl TryFrom<MpvDataType> for Vec<MpvDataType> {
+    type Error = Error;
+
+    fn try_from(value: MpvDataType) -> Result<Vec<MpvDataType>, Error> {
+        match value {
+            MpvDataType::Array(array) => Ok(array),
+            _ => Err(Error(ErrorCode::ValueDoesNotContainVec)),
+        }
+    }
+}
+
+impl TryFrom<MpvDataType> for bool {
+    type Error = Error;
+
+    fn try_from(value: MpvDataType) -> Result<bool, Error> {
+        match value {
+            MpvDataType::Bool(b) => Ok(b),
+            _ => Err(Error(ErrorCode::ValueDoesNotContainBool)),
+        }
+    }
+}
+
+impl TryFrom<MpvDataType> for String {
+    type Error = Error;
+
+    fn try_from(value: MpvDataType) -> Result<String, Error> {
+        match value {
+            MpvDataType::String(s) => Ok(s),
+            _ => Err(Error(ErrorCode::ValueDoesNotContainString)),
+        }
+    }
+}
+
+impl TryFrom<MpvDataType> for f64 {
+    type Error = Error;
+
+    fn try_from(value: MpvDataType) -> Result<f64, Error> {
+        match value {
+            MpvDataType::Double(d) => Ok(d),
+            MpvDataType::Usize(n) => Ok(n as f64),
+            MpvDataType::I64(n) => Ok(n as f64),
+            _ => Err(Error(ErrorCode::ValueDoesNotContainF64)),
+        }
+    }
+}
+
+impl TryFrom<MpvDataType> for usize {
+    type Error = Error;
+
+    fn try_from(value: MpvDataType) -> Result<usize, Error> {
+        match value {
+            MpvDataType::Usize(n) => Ok(n),
+            _ => Err(Error(ErrorCode::ValueDoesNotContainUsize)),
+        }
+    }
+}
+
+impl TryFrom<MpvDataType> for i64 {
+    type Error = Error;
+
+    fn try_from(value: MpvDataType) -> Result<i64, Error> {
+        match value {
+            MpvDataType::I64(n) => Ok(n),
+            MpvDataType::Usize(n) => {
+                i64::try_from(n).map_err(|_| Error(ErrorCode::ValueDoesNotContainI64))
+            }
+            _ => Err(Error(ErrorCode::ValueDoesNotContainI64)),
+        }
+    }
+}
+
+impl<'a> EventDispatcher<'a> {
+    pub fn new() -> EventDispatcher<'a> {
+        EventDispatcher {
+            handlers: Vec::new(),
+        }
+    }
+
+    /// Calls `callback` for every event.
+    pub fn on_event(&mut self, callback: impl FnMut(&Event) + 'a) -> &mut EventDispatcher<'a> {
+        self.handlers.push(Box::new(callback));
+        self
+    }
+
+    /// Calls `callback` for [Event::Pause].
+    pub fn on_pause(&mut self, mut callback: impl FnMut() + 'a) -> &mut EventDispatcher<'a> {
+        self.on_event(move |event| {
+            if let Event::Pause = event {
+                callback()
+            }
+        })
+    }
+
+    /// Calls `callback` for [Event::Unpause].
+    pub fn on_unpause(&mut self, mut callback: impl FnMut() + 'a) -> &mut EventDispatcher<'a> {
+        self.on_event(move |event| {
+            if let Event::Unpause = event {
+                callback()
+            }
+        })
+    }
+
+    /// Calls `callback` for [Event::FileLoaded].
+    pub fn on_file_loaded(&mut self, mut callback: impl FnMut() + 'a) -> &mut EventDispatcher<'a> {
+        self.on_event(move |event| {
+            if let Event::FileLoaded = event {
+                callback()
+            }
+        })
+    }
+
+    /// Calls `callback` with the reason of each [Event::EndFile].
+    pub fn on_end_file(
+        &mut self,
+        mut callback: impl FnMut(&EndFileReason) + 'a,
+    ) -> &mut EventDispatcher<'a> {
+        self.on_event(move |event| {
+            if let Event::EndFile { reason } = event {
+                callback(reason)
+            }
+        })
+    }
+
+    /// Calls `callback` with the new value whenever the observation `id` reports a
+    /// change, see [Mpv::observe_property].
+    pub fn on_property(
+        &mut self,
+        id: usize,
+        mut callback: impl FnMut(&Property) + 'a,
+    ) -> &mut EventDispatcher<'a> {
+        self.on_event(move |event| {
+            if let Event::PropertyChange {
+                id: changed,
+                property,
+            } = event
+            {
+                if *changed == id {
+                    callback(property)
+                }
+            }
+        })
+    }
+
+    /// # Description
+    ///
+    /// Reads the events mpv has sent so far without waiting for more and calls the
+    /// matching callbacks, in the order the callbacks were registered. Returns the
+    /// number of events read.
+    ///
+    /// If a line could not be decoded or the connection failed, the events read
+    /// before are still dispatched and the error is returned afterwards.
+    ///
+    /// Only supported on unix, on Windows this returns `ErrorCode::UnsupportedType`.
+    pub fn dispatch_pending(&mut self, mpv: &mut Mpv) -> Result<usize, Error> {
+        #[cfg(unix)]
+        {
+            let (events, error) = read_pending_events(mpv);
+            for event in &events {
+                for handler in &mut self.handlers {
+                    handler(event);
+                }
+            }
+            match error {
+                Some(error) => Err(error),
+                None => Ok(events.len()),
+            }
+        }
+        #[cfg(not(unix))]
+        {
+            let _ = mpv;
+            Err(Error(ErrorCode::UnsupportedType))
+        }
+    }
+}
+
+impl MpvBuilder {
+    pub fn new(socket: &str) -> MpvBuilder {
+        MpvBuilder {
+            socket: String::from(socket),
+            buffer_capacity: DEFAULT_BUFFER_CAPACITY,
+            handshake: false,
+        }
+    }
+
+    /// Makes [MpvBuilder::connect] check that mpv is listening on the socket by
+    /// reading `client-name`. If no valid reply arrives within a second, connecting
+    /// fails with `ErrorCode::ConnectError("not an mpv IPC socket")` instead of later
+    /// commands failing with confusing errors. Other failures, e.g. the socket being
+    /// closed right away, are returned as they are. Off by default to save the round
+    /// trip.
+    ///
+    /// Named pipes have no read timeout, so on Windows the reply is awaited on a
+    /// helper thread. If it doesn't arrive in time, that thread is left waiting until
+    /// the other side replies or closes the pipe.
+    pub fn handshake(mut self, enabled: bool) -> MpvBuilder {
+        self.handshake = enabled;
+        self
+    }
+
+    /// Sets the initial capacity of the buffer replies and events are read through.
+    /// Lines longer than the buffer are still read completely, a larger buffer
+    /// only saves reads for connections that regularly receive huge properties
+    /// like `track-list`.
+    pub fn buffer_capacity(mut self, capacity: usize) -> MpvBuilder {
+        self.buffer_capacity = capacity;
+        self
+    }
+
+    pub fn connect(self) -> Result<Mpv, Error> {
+        let handshake = self.handshake;
+        let mpv = self.connect_stream()?;
+        if !handshake {
+            return Ok(mpv);
+        }
+        #[cfg(unix)]
+        let checked = with_call_timeout(HANDSHAKE_TIMEOUT, || {
+            get_mpv_property::<String>(&mpv, "client-name")
+        })
+        .map(|_| mpv);
+        #[cfg(windows)]
+        let checked = {
+            let (sender, receiver) = channel();
+            thread::spawn(move || {
+                let client_name = get_mpv_property::<String>(&mpv, "client-name");
+                let _ = sender.send(client_name.map(|_| mpv));
+            });
+            receiver
+                .recv_timeout(HANDSHAKE_TIMEOUT)
+                .unwrap_or(Err(Error(ErrorCode::Timeout)))
+        };
+        match checked {
+            // something answered, but not the way mpv does, or nothing answered
+            Err(Error(
+                ErrorCode::Timeout
+                | ErrorCode::JsonParseError(_)
+                | ErrorCode::MpvError(_)
+                | ErrorCode::UnexpectedResult
+                | ErrorCode::UnexpectedValue
+                | ErrorCode::ValueDoesNotContainString,
+            )) => Err(Error(ErrorCode::ConnectError(
+                "not an mpv IPC socket".to_string(),
+            ))),
+            checked => checked,
+        }
+    }
+
+    fn connect_stream(self) -> Result<Mpv, Error> {
+        match LocalSocketStream::connect(local_socket_name(&self.socket)) {
+            Ok(stream) => self.with_stream(stream),
+            Err(internal_error) => Err(Error(ErrorCode::ConnectError(internal_error.to_string()))),
+        }
+    }
+
+    /// Builds the connection around an already connected `stream`, see
+    /// [Mpv::from_raw_fd].
+    fn with_stream(self, stream: LocalSocketStream) -> Result<Mpv, Error> {
+        let duplicate = || {
+            duplicate_stream(&stream).map_err(|why| Error(ErrorCode::ConnectError(why.to_string())))
+        };
+        let cloned_stream = duplicate()?;
+        let shutdown = ShutdownOnDrop {
+            stream: Mutex::new(Some(duplicate()?)),
+        };
+        Ok(Mpv {
+            stream,
+            reader: Mutex::new(LineReader::new(BufReader::with_capacity(
+                self.buffer_capacity,
+                cloned_stream,
+            ))),
+            name: self.socket,
+            observations: Arc::new(Mutex::new(HashMap::new())),
+            next_observe_id: Arc::new(AtomicIsize::new(1)),
+            hooks: Arc::new(Mutex::new(HashMap::new())),
+            next_hook_id: Arc::new(AtomicU64::new(1)),
+            next_request_id: Arc::new(AtomicU64::new(1)),
+            poisoned: Arc::new(AtomicBool::new(false)),
+            replies: None,
+            timeout: Mutex::new(None),
+            shutdown: Arc::new(shutdown),
+        })
+    }
+}
+
+impl Mpv {
+    /// Connects to the socket mpv was started with (`--input-ipc-server`).
+    ///
+    /// On Windows `socket` is the name of the named pipe. Like mpv, both the short
+    /// form (`mpvsocket`) and the full path (`\\.\pipe\mpvsocket`) are accepted.
+    pub fn connect(socket: &str) -> Result<Mpv, Error> {
+        MpvBuilder::new(socket).connect()
+    }
+
+    /// # Description
+    ///
+    /// Builds a connection around a socket that is already connected to mpv, e.g.
+    /// one passed in by a sandbox broker or by systemd socket activation. `name` is
+    /// only used to describe the connection, see [Mpv::socket_path].
+    ///
+    /// The connection takes ownership of `fd` and closes it when the last clone is
+    /// dropped, so the caller must not close or use it afterwards.
+    ///
+    /// # Safety
+    ///
+    /// `fd` must be an open unix socket that nothing else owns.
+    #[cfg(unix)]
+    pub unsafe fn from_raw_fd(fd: std::os::fd::RawFd, name: String) -> Result<Mpv, Error> {
+        let stream = unsafe { LocalSocketStream::from_raw_fd(fd) };
+        MpvBuilder::new(&name).with_stream(stream)
+    }
+
+    /// # Description
+    ///
+    /// Builds a connection around a named pipe that is already connected to mpv, the
+    /// Windows counterpart to `from_raw_fd`. `name` is only used to describe the
+    /// connection.
+    ///
+    /// The connection takes ownership of `handle` and closes it when the last clone
+    /// is dropped, so the caller must not close or use it afterwards.
+    ///
+    /// # Safety
+    ///
+    /// `handle` must be an open named pipe handle that nothing else owns.
+    #[cfg(windows)]
+    pub unsafe fn from_raw_handle(
+        handle: std::os::windows::io::RawHandle,
+        name: String,
+    ) -> Result<Mpv, Error> {
+        let stream = unsafe { LocalSocketStream::from_raw_handle(handle) };
+        MpvBuilder::new(&name).with_stream(stream)
+    }
+
+    /// Connects to the socket named by the `MPV_SOCKET` environment variable, or to
+    /// [DEFAULT_SOCKET] if it is unset or empty. mpv has to be started with the same
+    /// socket, e.g. `mpv --input-ipc-server="$MPV_SOCKET"`.
+    pub fn connect_default() -> Result<Mpv, Error> {
+        match std::env::var("MPV_SOCKET") {
+            Ok(socket) if !socket.is_empty() => Mpv::connect(&socket),
+            _ => Mpv::connect(DEFAULT_SOCKET),
+        }
+    }
+
+    fn buffer_capacity(&self) -> usize {
+        self.reader
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .capacity()
+    }
+
+    /// # Description
+    ///
+    /// Like [Clone::clone], but returns `ErrorCode::ConnectError` instead of
+    /// panicking if the socket can't be duplicated, e.g. because the process ran out
+    /// of file descriptors. The clone shares the connection and its observations.
+    pub fn try_clone(&self) -> Result<Mpv, Error> {
+        let duplicate = || {
+            duplicate_stream(&self.stream)
+                .map_err(|why| Error(ErrorCode::ConnectError(why.to_string())))
+        };
+        let stream = duplicate()?;
+        let cloned_stream = duplicate()?;
+        Ok(Mpv {
+            stream,
+            reader: Mutex::new(LineReader::new(BufReader::with_capacity(
+                self.buffer_capacity(),
+                cloned_stream,
+            ))),
+            name: self.name.clone(),
+            observations: self.observations.clone(),
+            next_observe_id: self.next_observe_id.clone(),
+            hooks: self.hooks.clone(),
+            next_hook_id: self.next_hook_id.clone(),
+            next_request_id: self.next_request_id.clone(),
+            poisoned: self.poisoned.clone(),
+            replies: self.replies.clone(),
+            timeout: Mutex::new(self.timeout()),
+            shutdown: self.shutdown.clone(),
+        })
+    }
+
+    /// # Description
+    ///
+    /// Connects to the same socket again, e.g. after mpv was restarted and requests
+    /// failed with `ErrorCode::Disconnected`. The observations of this connection are
+    /// registered again with the same ids, so `PropertyChange` events keep arriving
+    /// as before and report the current values first.
+    ///
+    /// Properties observed with [Mpv::observe_property_string] are observed as
+    /// strings again. Only this instance is reconnected; clones made before keep the
+    /// old connection. Hooks are not added again, they have to be added with
+    /// [Mpv::hook_add] after reconnecting. If an observation can't be registered, the error is
+    /// returned and this instance keeps the old connection.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use mpvipc::{Error, ErrorCode, Mpv};
+    /// # fn main() -> Result<(), Error> {
+    /// let mut mpv = Mpv::connect("/tmp/mpvsocket")?;
+    /// mpv.observe_property(1, "pause")?;
+    /// loop {
+    ///     match mpv.event_listen() {
+    ///         Ok(event) => println!("{:?}", event),
+    ///         Err(Error(ErrorCode::Disconnected)) => mpv.reconnect()?,
+    ///         Err(error) => return Err(error),
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    pub fn reconnect(&mut self) -> Result<(), Error> {
+        let observations = self
+            .observations
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+        let mpv = MpvBuilder::new(&self.name)
+            .buffer_capacity(self.buffer_capacity())
+            .connect()?;
+        mpv.set_timeout(self.timeout());
+        restore_observations(&mpv, &observations)?;
+        log_lost_hooks(&self.hooks.lock().unwrap_or_else(PoisonError::into_inner));
+        *self = mpv;
+        Ok(())
+    }
+
+    /// Shuts the connection down right away, for this instance and all its clones,
+    /// and ignores errors. Use [Mpv::close] to leave it open for the other clones.
+    pub fn disconnect(&self) {
+        let _ = self.shutdown.shut_down();
+    }
+
+    /// # Description
+    ///
+    /// Flushes and shuts down the connection, returning any error that happened on the
+    /// way; dropping an [Mpv] does the same but has to ignore errors.
+    ///
+    /// Clones share the connection, so it is only shut down once the last of them is
+    /// closed or dropped.
+    pub fn close(self) -> Result<(), Error> {
+        match Arc::try_unwrap(self.shutdown) {
+            Ok(shutdown) => shutdown.shut_down(),
+            // a clone is still around, the last one shuts the connection down
+            Err(_) => Ok(()),
+        }
+    }
+
+    /// Returns the timeout set with [Mpv::set_timeout].
+    pub fn timeout(&self) -> Option<Duration> {
+        *self.timeout.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// # Description
+    ///
+    /// Checks that mpv is still there by reading `client-name`, which costs mpv next
+    /// to nothing. Fails with `ErrorCode::Disconnected` if mpv closed the connection,
+    /// e.g. because it quit, and with `ErrorCode::Timeout` if it didn't reply within
+    /// `timeout`, e.g. because it hangs. Like [Mpv::set_timeout], the timeout only
+    /// applies after [Mpv::into_event_thread] on Windows.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use mpvipc::{Error, ErrorCode, Mpv};
+    /// # use std::time::Duration;
+    /// # fn main() -> Result<(), Error> {
+    /// let mut mpv = Mpv::connect("/tmp/mpvsocket")?;
+    /// loop {
+    ///     std::thread::sleep(Duration::from_secs(10));
+    ///     match mpv.ping(Duration::from_secs(1)) {
+    ///         Err(Error(ErrorCode::Disconnected)) => mpv.reconnect()?,
+    ///         result => result?,
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    pub fn ping(&self, timeout: Duration) -> Result<(), Error> {
+        with_call_timeout(timeout, || get_mpv_property::<String>(self, "client-name")).map(|_| ())
+    }
+
+    /// Returns the socket path this instance was connected to.
+    pub fn socket_path(&self) -> &str {
+        &self.name
+    }
+
+    /// # Description
+    ///
+    /// Returns a handle that can end blocking reads like [Mpv::event_listen] from
+    /// another thread, e.g. to stop a listener thread on exit without quitting mpv.
+    /// Once signaled, the connection is closed for good.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let mut mpv = Mpv::connect("/tmp/mpvsocket")?;
+    /// let shutdown = mpv.shutdown_handle();
+    /// let listener = std::thread::spawn(move || while mpv.event_listen().is_ok() {});
+    /// // ...
+    /// shutdown.signal()?;
+    /// listener.join().unwrap();
+    /// ```
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        ShutdownHandle {
+            stream: duplicate_stream(&self.stream).expect("could not duplicate the mpv socket"),
+        }
+    }
+
+    pub fn get_stream_ref(&self) -> &LocalSocketStream {
+        &self.stream
+    }
+
+    /// # Description
+    ///
+    /// Gives direct access to the buffered read side of the socket, for experiments
+    /// with reply or event handling this crate doesn't offer.
+    ///
+    /// This is an escape hatch: all other methods expect to read complete lines
+    /// themselves. Reading part of a line, or a reply another method is waiting for,
+    /// leaves the connection out of sync with the requests sent. The rest of a line
+    /// cut off by a timeout is not part of the returned reader either.
+    pub fn reader_mut(&mut self) -> &mut BufReader<LocalSocketStream> {
+        self.reader
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get_mut()
+    }
+
+    /// # Description
+    ///
+    /// Reads the parameters of the current audio output (`audio-params`).
+    /// Returns [AudioParams::default] if there is no audio.
+    pub fn audio_params(&self) -> Result<AudioParams, Error> {
+        let params = get_mpv_properties::<Value>(self, &["audio-params"])?
+            .remove(0)
+            .unwrap_or_default();
+        Ok(AudioParams {
+            samplerate: params["samplerate"].as_u64().map(|n| n as usize),
+            channel_count: params["channel-count"].as_u64().map(|n| n as usize),
+            channels: params["channels"].as_str().map(str::to_string),
+            format: params["format"].as_str().map(str::to_string),
+        })
+    }
+
+    /// # Description
+    ///
+    /// Reads the state of the demuxer cache (`demuxer-cache-state`), e.g. to shade
+    /// the buffered parts of a seek bar. Returns [CacheState::default] if nothing
+    /// is loaded.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use mpvipc::{Mpv, Error};
+    /// # fn main() -> Result<(), Error> {
+    /// let mpv = Mpv::connect("/tmp/mpvsocket")?;
+    /// for (start, end) in mpv.get_cache_state()?.seekable_ranges {
+    ///     println!("buffered from {} to {}", start, end);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_cache_state(&self) -> Result<CacheState, Error> {
+        let mut values =
+            get_mpv_properties::<Value>(self, &["demuxer-cache-state", "demuxer-cache-duration"])?
+                .into_iter()
+                .map(Option::unwrap_or_default);
+        let state = values.next().unwrap_or_default();
+        let duration = values.next().and_then(|duration| duration.as_f64());
+        Ok(CacheState {
+            fw_bytes: state["fw-bytes"].as_u64().unwrap_or(0),
+            cache_duration: state["cache-duration"].as_f64().or(duration),
+            underrun: state["underrun"].as_bool().unwrap_or(false),
+            seekable_ranges: seekable_ranges(&state["seekable-ranges"]),
+        })
+    }
+
+    /// # Description
+    ///
+    /// Derives a single [PlaybackState] from `idle-active`, `eof-reached`,
+    /// `paused-for-cache`, `pause` and `core-idle`, read in one round trip. The
+    /// properties are checked in that order and the first one that is set decides
+    /// the state, e.g. a user pause at the end of a file is [PlaybackState::Ended].
+    /// If none is set, the player is [PlaybackState::Playing].
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use mpvipc::{Mpv, Error, PlaybackState};
+    /// # fn main() -> Result<(), Error> {
+    /// let mpv = Mpv::connect("/tmp/mpvsocket")?;
+    /// if mpv.playback_state()? == PlaybackState::Buffering {
+    ///     println!("waiting for the network");
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn playback_state(&self) -> Result<PlaybackState, Error> {
+        let flags: Vec<bool> = get_mpv_properties::<bool>(
+            self,
+            &[
+                "idle-active",
+                "eof-reached",
+                "paused-for-cache",
+                "pause",
+                "core-idle",
+            ],
+        )?
+        .into_iter()
+        .map(|flag| flag.unwrap_or(false))
+        .collect();
+        Ok(match flags[..] {
+            [true, ..] => PlaybackState::Idle,
+            [_, true, ..] => PlaybackState::Ended,
+            [_, _, true, ..] => PlaybackState::Buffering,
+            [_, _, _, true, _] => PlaybackState::Paused,
+            [_, _, _, _, true] => PlaybackState::Loading,
+            _ => PlaybackState::Playing,
+        })
+    }
+
+    /// # Description
+    ///
+    /// Reads only the `seekable-ranges` of the demuxer cache, see
+    /// [CacheState::seekable_ranges]. Returns an empty list if nothing is cached.
+    pub fn seekable_ranges(&self) -> Result<Vec<(f64, f64)>, Error> {
+        let ranges = get_mpv_properties::<Value>(self, &["demuxer-cache-state/seekable-ranges"])?
+            .remove(0)
+            .unwrap_or_default();
+        Ok(seekable_ranges(&ranges))
+    }
+
+    /// # Description
+    ///
+    /// Sets _<property>_ to `new` if its current value equals `expected`.
+    /// Returns whether the property was set.
+    ///
+    /// The property is read and written with two separate commands, so another
+    /// client can still change it in between.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use mpvipc::{Mpv, Error};
+    /// use serde_json::json;
+    /// fn main() -> Result<(), Error> {
+    ///     let mpv = Mpv::connect("/tmp/mpvsocket")?;
+    ///     // pause only if currently playing
+    ///     mpv.compare_and_set("pause", json!(false), json!(true))?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn compare_and_set(
+        &self,
+        property: &str,
+        expected: Value,
+        new: Value,
+    ) -> Result<bool, Error> {
+        let current = get_mpv_property::<Value>(self, property)?;
+        if !same_value(&current, &expected) {
+            return Ok(false);
+        }
+        set_mpv_property(self, property, new)?;
+        Ok(true)
+    }
+
+    pub fn get_edition_list(&self) -> Result<Vec<Edition>, Error> {
+        get_mpv_property::<Vec<Edition>>(self, "edition-list")
+    }
+
+    /// Returns the audio, video and subtitle tracks of the current file
+    /// (`track-list`).
+    pub fn get_track_list(&self) -> Result<Vec<Track>, Error> {
+        get_mpv_property::<Vec<Track>>(self, "track-list")
+    }
+
+    /// # Description
+    ///
+    /// Returns the selected track of the given type, or `None` if no track of this
+    /// type is selected, e.g. while subtitles are off.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use mpvipc::{Mpv, Error, TrackType};
+    /// # fn main() -> Result<(), Error> {
+    /// let mpv = Mpv::connect("/tmp/mpvsocket")?;
+    /// if let Some(audio) = mpv.current_track(TrackType::Audio)? {
+    ///     println!("audio language: {}", audio.lang.unwrap_or_default());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn current_track(&self, track_type: TrackType) -> Result<Option<Track>, Error> {
+        Ok(self
+            .get_track_list()?
+            .into_iter()
+            .find(|track| track.track_type == track_type && track.selected))
+    }
+
+    /// # Description
+    ///
+    /// Selects the next subtitle track, going from the last track to "off" and from
+    /// "off" to the first track. Returns the new selection. Unlike `cycle sid`, "off"
+    /// is always part of the cycle, independent of the mpv version.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use mpvipc::{Mpv, Error, TrackSelection};
+    /// # fn main() -> Result<(), Error> {
+    /// let mpv = Mpv::connect("/tmp/mpvsocket")?;
+    /// match mpv.cycle_subtitle()? {
+    ///     TrackSelection::Off => mpv.show_text("Subtitles off")?,
+    ///     TrackSelection::Track(id) => mpv.show_text(&format!("Subtitle {}", id))?,
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn cycle_subtitle(&self) -> Result<TrackSelection, Error> {
+        let tracks = self.get_track_list()?;
+        let mut subtitles: Vec<&Track> = tracks
+            .iter()
+            .filter(|track| track.track_type == TrackType::Sub)
+            .collect();
+        subtitles.sort_by_key(|track| track.id);
+        // `selected` is also set for the secondary subtitle, `sid` is not
+        let current = get_mpv_property::<Value>(self, "sid")?.as_u64();
+        let next = match subtitles
+            .iter()
+            .position(|track| Some(track.id as u64) == current)
+        {
+            Some(index) => subtitles.get(index + 1),
+            None => subtitles.first(),
+        };
+        match next {
+            Some(track) => {
+                set_mpv_property(self, "sid", json!(track.id))?;
+                Ok(TrackSelection::Track(track.id))
+            }
+            None => {
+                set_mpv_property(self, "sid", json!("no"))?;
+                Ok(TrackSelection::Off)
+            }
+        }
+    }
+
+    pub fn get_metadata(&self) -> Result<HashMap<String, MpvDataType>, Error> {
+        match get_mpv_property(self, "metadata") {
+            Ok(map) => Ok(map),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// # Description
+    ///
+    /// Returns the metadata of the current chapter (`chapter-metadata`), e.g. the
+    /// title and artist of an audiobook chapter. Values that are not strings are
+    /// formatted as JSON, e.g. `3` or `true`. The map is empty if the file has no
+    /// chapters.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use mpvipc::{Mpv, Error};
+    /// # fn main() -> Result<(), Error> {
+    /// let mpv = Mpv::connect("/tmp/mpvsocket")?;
+    /// if let Some(title) = mpv.chapter_metadata()?.get("title") {
+    ///     println!("chapter: {}", title);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn chapter_metadata(&self) -> Result<HashMap<String, String>, Error> {
+        let metadata = get_mpv_properties::<Value>(self, &["chapter-metadata"])?.remove(0);
+        Ok(metadata_strings(metadata.unwrap_or_default()))
+    }
+
+    pub fn get_playlist(&self) -> Result<Playlist, Error> {
+        match get_mpv_property::<Vec<PlaylistEntry>>(self, "playlist") {
+            Ok(entries) => Ok(Playlist(entries)),
+            Err(msg) => Err(msg),
+        }
+    }
+
+    /// # Description
+    ///
+    /// Reads a single field of the playlist entry at `index` through the
+    /// `playlist/<index>/<field>` property, e.g. `title` or `filename`, without
+    /// fetching the whole playlist.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use mpvipc::{Mpv, Error};
+    /// # fn main() -> Result<(), Error> {
+    /// let mpv = Mpv::connect("/tmp/mpvsocket")?;
+    /// let filename: String = mpv.get_playlist_entry_field(2, "filename")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_playlist_entry_field<T: GetPropertyTypeHandler>(
+        &self,
+        index: usize,
+        field: &str,
+    ) -> Result<T, Error> {
+        T::get_property_generic(self, &format!("playlist/{}/{}", index, field))
+    }
+
+    /// # Description
+    ///
+    /// Returns `(id, title)` for every playlist entry, ready to be displayed. Entries
+    /// without a title use the last component of their filename, see
+    /// [PlaylistEntry::display_title]. Use [Mpv::get_playlist] for the exact data.
+    pub fn get_playlist_resolved(&self) -> Result<Vec<(usize, String)>, Error> {
+        let Playlist(entries) = self.get_playlist()?;
+        Ok(entries
+            .iter()
+            .map(|entry| (entry.id, entry.display_title()))
+            .collect())
+    }
+
+    /// # Description
+    ///
+    /// Writes the playlist to `writer` as an extended M3U file. Each entry is written
+    /// as its absolute path (see [Mpv::export_playlist]) or URL, preceded by an
+    /// `#EXTINF` line with its title if it has one. mpv only knows the duration of
+    /// the current file, the other entries are written with the duration `-1`
+    /// (unknown).
+    ///
+    /// M3U has no escaping. Line breaks in titles are replaced by spaces, an entry
+    /// whose filename contains a line break fails with `ErrorCode::UnexpectedValue`
+    /// before anything is written. If `writer` fails, `ErrorCode::IoError` is
+    /// returned.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use mpvipc::{Mpv, Error};
+    /// # use std::fs::File;
+    /// # fn main() -> Result<(), Error> {
+    /// let mpv = Mpv::connect("/tmp/mpvsocket")?;
+    /// let file = File::create("queue.m3u").expect("could not create queue.m3u");
+    /// mpv.save_playlist_m3u(file)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn save_playlist_m3u<W: Write>(&self, mut writer: W) -> Result<(), Error> {
+        let entries = self.export_playlist()?;
+        let duration = get_mpv_properties::<f64>(self, &["duration"])?.remove(0);
+        let mut m3u = String::from("#EXTM3U\n");
+        for entry in entries {
+            let location = match entry.resolved {
+                Some(path) => path.to_string_lossy().into_owned(),
+                None => entry.filename,
+            };
+            if location.contains(['\n', '\r']) {
+                return Err(Error(ErrorCode::UnexpectedValue));
+            }
+            let seconds = match duration {
+                Some(duration) if entry.current => duration.round() as i64,
+                _ => -1,
+            };
+            if let Some(title) = entry.title {
+                let title = title.replace(['\n', '\r'], " ");
+                m3u.push_str(&format!("#EXTINF:{},{}\n", seconds, title));
+            } else if seconds >= 0 {
+                m3u.push_str(&format!("#EXTINF:{},\n", seconds));
+            }
+            m3u.push_str(&location);
+            m3u.push('\n');
+        }
+        writer
+            .write_all(m3u.as_bytes())
+            .and_then(|()| writer.flush())
+            .map_err(|why| Error(ErrorCode::IoError(why.to_string())))
+    }
+
+    /// # Description
+    ///
+    /// Returns the playlist prepared for saving it, e.g. as a session. Relative
+    /// filenames are resolved against mpv's `working-directory`, so the paths stay
+    /// valid for other processes. URLs are kept as they are, with `resolved` set
+    /// to `None`.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use mpvipc::{Mpv, Error};
+    /// # fn main() -> Result<(), Error> {
+    /// let mpv = Mpv::connect("/tmp/mpvsocket")?;
+    /// for entry in mpv.export_playlist()? {
+    ///     match entry.resolved {
+    ///         Some(path) => println!("{}", path.display()),
+    ///         None => println!("{}", entry.filename),
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn export_playlist(&self) -> Result<Vec<ExportEntry>, Error> {
+        let Playlist(entries) = self.get_playlist()?;
+        let working_directory = get_mpv_properties::<String>(self, &["working-directory"])?
+            .remove(0)
+            .map(PathBuf::from);
+        Ok(entries
+            .into_iter()
+            .map(|entry| ExportEntry {
+                resolved: resolve_filename(&entry.filename, working_directory.as_deref()),
+                title: Some(entry.title).filter(|title| !title.is_empty()),
+                current: entry.current,
+                filename: entry.filename,
+            })
+            .collect())
+    }
+
+    /// Works like [Mpv::get_property] for one of the [KnownProperty] variants.
+    pub fn get<T: GetPropertyTypeHandler>(&self, property: KnownProperty) -> Result<T, Error> {
+        T::get_property_generic(self, property.as_str())
+    }
+
+    /// # Description
+    ///
+    /// Retrieves the property value from mpv.
+    ///
+    /// ## Supported types
+    /// - String
+    /// - bool
+    /// - HashMap<String, String> (e.g. for the 'metadata' property)
+    /// - Vec<PlaylistEntry> (for the 'playlist' property)
+    /// - Vec<Edition> (for the 'edition-list' property)
+    /// - Vec<Track> (for the 'track-list' property)
+    /// - Vec<String> (e.g. for the 'property-list' property)
+    /// - usize
+    /// - i64
+    /// - f64
+    ///
+    /// ## Input arguments
+    ///
+    /// - **property** defines the mpv property that should be retrieved
+    ///
+    /// # Example
+    /// ```no_run
+    /// use mpvipc::{Mpv, Error};
+    /// fn main() -> Result<(), Error> {
+    ///     let mpv = Mpv::connect("/tmp/mpvsocket")?;
+    ///     let paused: bool = mpv.get_property("pause")?;
+    ///     let title: String = mpv.get_property("media-title")?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn get_property<T: GetPropertyTypeHandler>(&self, property: &str) -> Result<T, Error> {
+        T::get_property_generic(self, property)
+    }
+
+    /// Works like [Mpv::get_property], but waits at most `timeout` for the reply
+    /// instead of the timeout set with [Mpv::set_timeout].
+    pub fn get_property_timeout<T: GetPropertyTypeHandler>(
+        &self,
+        property: &str,
+        timeout: Duration,
+    ) -> Result<T, Error> {
+        with_call_timeout(timeout, || T::get_property_generic(self, property))
+    }
+
+    /// # Description
+    ///
+    /// Reads several properties at once. All requests are sent before the first
+    /// reply is read, so this costs a single round trip instead of one per property.
+    /// The values are returned in the order of `properties`, unavailable properties
+    /// as `MpvDataType::Null`.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use mpvipc::{Mpv, Error};
+    /// # fn main() -> Result<(), Error> {
+    /// let mpv = Mpv::connect("/tmp/mpvsocket")?;
+    /// let values = mpv.get_properties(&["pause", "time-pos", "volume"])?;
+    /// println!("{:?}", values);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_properties(&self, properties: &[&str]) -> Result<Vec<MpvDataType>, Error> {
+        get_mpv_properties::<Value>(self, properties)?
+            .into_iter()
+            .zip(properties)
+            .map(|(value, property)| json_to_data(property, &value.unwrap_or_default()))
+            .collect()
+    }
+
+    /// # Description
+    ///
+    /// Retrieves the property value from mpv.
+    /// The result is always of type String, regardless of the type of the value of the mpv property
+    ///
+    /// The conversion is done by mpv, so the string is formatted the same way mpv
+    /// formats it elsewhere (e.g. in `show-text ${...}`):
+    /// - flags are `yes` or `no`
+    /// - numbers are printed the way mpv prints them, e.g. `0.500000` for `speed`
+    /// - choices and strings are returned as they are, e.g. `inf` for `loop-file`
+    ///
+    /// If mpv can't read the property as a string, because it is unknown, unavailable
+    /// or can't be converted, the result is `ErrorCode::MissingValue`. mpv replies the
+    /// same way in all these cases, so they can't be told apart.
+    ///
+    /// ## Input arguments
+    ///
+    /// - **property** defines the mpv property that should be retrieved
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use mpvipc::{Mpv, Error};
+    /// fn main() -> Result<(), Error> {
     ///     let mpv = Mpv::connect("/tmp/mpvsocket")?;
     ///     let title = mpv.get_property_string("media-title")?;
     ///     Ok(())
@@ -410,6 +2227,127 @@ impl Mpv {
         get_mpv_property_string(self, property)
     }
 
+    /// # Description
+    ///
+    /// Returns whether this mpv build knows _<property>_, e.g. for feature detection.
+    /// Properties that exist but currently have no value (mpv reports them as
+    /// unavailable, e.g. `duration` while idle) count as existing.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use mpvipc::{Mpv, Error};
+    /// # fn main() -> Result<(), Error> {
+    /// let mpv = Mpv::connect("/tmp/mpvsocket")?;
+    /// if mpv.has_property("secondary-sub-visibility")? {
+    ///     mpv.set_property("secondary-sub-visibility", false)?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn has_property(&self, property: &str) -> Result<bool, Error> {
+        match get_mpv_property::<Value>(self, property) {
+            Ok(_) => Ok(true),
+            Err(Error(ErrorCode::MpvError(ref error))) if error == "property not found" => {
+                Ok(false)
+            }
+            Err(Error(ErrorCode::MpvError(ref error))) if error == "property unavailable" => {
+                Ok(true)
+            }
+            Err(error) => Err(error),
+        }
+    }
+
+    /// # Description
+    ///
+    /// Reads _<property>_ once and returns the type of its value, see
+    /// [MpvDataType::type_name]. mpv has no command to ask for the type directly,
+    /// so an unavailable property fails with `ErrorCode::MpvError`.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use mpvipc::{Mpv, Error};
+    /// # fn main() -> Result<(), Error> {
+    /// let mpv = Mpv::connect("/tmp/mpvsocket")?;
+    /// assert_eq!(mpv.property_type("pause")?, "boolean");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn property_type(&self, property: &str) -> Result<&'static str, Error> {
+        let value = get_mpv_property::<Value>(self, property)?;
+        Ok(json_to_data(property, &value)?.type_name())
+    }
+
+    /// # Description
+    ///
+    /// Registers the hook _<name>_ (e.g. `on_load` or `on_preloaded`) with the given
+    /// priority and returns the id it was registered with.
+    ///
+    /// Every time the hook runs, [Mpv::event_listen] returns an [Event::Hook]. mpv
+    /// blocks until the hook is acknowledged with [Mpv::hook_ack], so the event loop
+    /// must acknowledge every hook event, even if it does nothing with it.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let mut mpv = Mpv::connect("/tmp/mpvsocket")?;
+    /// mpv.hook_add("on_load", 50)?;
+    /// loop {
+    ///     if let Event::Hook { name, hook_id } = mpv.event_listen()? {
+    ///         let url: String = mpv.get_property("stream-open-filename")?;
+    ///         mpv.set_property("stream-open-filename", resolve(url))?;
+    ///         mpv.hook_ack(hook_id)?;
+    ///     }
+    /// }
+    /// ```
+    pub fn hook_add(&self, name: &str, priority: i64) -> Result<u64, Error> {
+        let id = self.next_hook_id.fetch_add(1, Ordering::Relaxed);
+        run_mpv_command(
+            self,
+            "hook-add",
+            &[name, &id.to_string(), &priority.to_string()],
+        )?;
+        self.hooks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(id, name.to_string());
+        Ok(id)
+    }
+
+    /// Lets mpv continue after an [Event::Hook] with the given `hook_id`.
+    pub fn hook_ack(&self, hook_id: u64) -> Result<(), Error> {
+        run_mpv_command(self, "hook-ack", &[&hook_id.to_string()])
+    }
+
+    /// Returns whether playback is currently stalled (`core-idle`), even if not paused.
+    pub fn is_core_idle(&self) -> Result<bool, Error> {
+        get_mpv_property(self, "core-idle")
+    }
+
+    /// Returns whether playback is paused to fill the cache (`paused-for-cache`).
+    pub fn is_paused_for_cache(&self) -> Result<bool, Error> {
+        get_mpv_property(self, "paused-for-cache")
+    }
+
+    /// # Description
+    ///
+    /// Returns the names of all top-level properties this mpv build knows
+    /// (`property-list`). Sub-properties like `video-params/w` are not listed.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use mpvipc::{Mpv, Error};
+    /// # fn main() -> Result<(), Error> {
+    /// let mpv = Mpv::connect("/tmp/mpvsocket")?;
+    /// for name in mpv.property_list()? {
+    ///     println!("{}: {}", name, mpv.get_property_string(&name).unwrap_or_default());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn property_list(&self) -> Result<Vec<String>, Error> {
+        get_mpv_property::<Vec<String>>(self, "property-list")
+    }
+
     pub fn kill(&self) -> Result<(), Error> {
         self.run_command(MpvCommand::Quit)
     }
@@ -431,21 +2369,356 @@ impl Mpv {
         listen(self)
     }
 
-    pub fn event_listen_raw(&mut self) -> String {
+    /// # Description
+    ///
+    /// Returns an iterator over the events of this connection, which waits for each
+    /// event like [Mpv::event_listen] and ends once mpv closes the connection.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let mut mpv = Mpv::connect("/tmp/mpvsocket")?;
+    /// mpv.observe_property(1, "volume")?;
+    /// for change in mpv.events().property_changes() {
+    ///     let (id, property) = change?;
+    ///     println!("{}: {:?}", id, property);
+    /// }
+    /// ```
+    pub fn events(&mut self) -> Events<'_> {
+        Events { mpv: self }
+    }
+
+    /// # Description
+    ///
+    /// Waits until an mpv event occurs and returns the parsed event object.
+    /// Unlike [Mpv::event_listen], all fields sent by mpv are kept, which is useful
+    /// for events or fields that are not modeled by [Event] yet.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let mut mpv = Mpv::connect("/tmp/mpvsocket")?;
+    /// let event = mpv.event_listen_json()?;
+    /// if event["event"] == "end-file" {
+    ///     println!("reason: {}", event["reason"]);
+    /// }
+    /// ```
+    pub fn event_listen_json(&mut self) -> Result<Value, Error> {
+        listen_json(self)
+    }
+
+    pub fn event_listen_raw(&mut self) -> Result<String, Error> {
         listen_raw(self)
     }
 
+    /// # Description
+    ///
+    /// Moves reading events to a background thread, which sends them to the returned
+    /// receiver. Commands can still be issued through the [EventHandle]; their replies
+    /// are handed over by the thread.
+    ///
+    /// The thread sends `Err(Error(ErrorCode::Disconnected))` and exits once mpv closes
+    /// the connection, and exits as well when the handle is dropped. If only the
+    /// receiver is dropped, the thread keeps reading and discards the events, so
+    /// commands through the handle still get their replies.
+    ///
+    /// Fails with `ErrorCode::ConnectError` if the socket can't be duplicated for the
+    /// handle.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let (mpv, events) = Mpv::connect("/tmp/mpvsocket")?.into_event_thread()?;
+    /// mpv.observe_property(1, "pause")?;
+    /// for event in events {
+    ///     println!("{:?}", event?);
+    /// }
+    /// ```
+    pub fn into_event_thread(self) -> Result<(EventHandle, Receiver<Result<Event, Error>>), Error> {
+        let (event_sender, events) = channel();
+        let (reply_sender, replies) = channel();
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let mut mpv = self.try_clone()?;
+        mpv.replies = Some(Arc::new(Mutex::new(replies)));
+        // the thread keeps the original reader so nothing it already buffered is lost
+        let routes = PropertyRoutes::default();
+        let thread_routes = routes.clone();
+        let thread_stop = stop.clone();
+        thread::spawn(move || {
+            run_event_thread(self, event_sender, reply_sender, thread_routes, thread_stop)
+        });
+
+        Ok((EventHandle { mpv, stop, routes }, events))
+    }
+
+    /// # Description
+    ///
+    /// Loads the first of `files` according to `first` (replacing the playlist or
+    /// appending to it) and appends the rest, all with a single round trip.
+    ///
+    /// mpv handles every file on its own, so if one fails, the others are still
+    /// added. The returned `ErrorCode::MpvError` names the first file that failed.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use mpvipc::{Mpv, Error, PlaylistAddOptions};
+    /// # fn main() -> Result<(), Error> {
+    /// let mpv = Mpv::connect("/tmp/mpvsocket")?;
+    /// mpv.load_files(&["01.flac", "02.flac", "03.flac"], PlaylistAddOptions::Replace)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn load_files(&self, files: &[&str], first: PlaylistAddOptions) -> Result<(), Error> {
+        let first = match first {
+            PlaylistAddOptions::Append => "append",
+            PlaylistAddOptions::Replace => "replace",
+        };
+        let commands: Vec<Vec<Value>> = files
+            .iter()
+            .enumerate()
+            .map(|(i, file)| {
+                let option = if i == 0 { first } else { "append" };
+                vec![json!("loadfile"), json!(file), json!(option)]
+            })
+            .collect();
+        let replies = send_mpv_requests(self, &commands)?;
+        for (file, reply) in files.iter().zip(replies) {
+            match reply["error"].as_str() {
+                Some("success") => {}
+                Some(error) => {
+                    return Err(Error(ErrorCode::MpvError(format!("{}: {}", file, error))))
+                }
+                None => return Err(Error(ErrorCode::UnexpectedResult)),
+            }
+        }
+        Ok(())
+    }
+
     pub fn next(&self) -> Result<(), Error> {
         self.run_command(MpvCommand::PlaylistNext)
     }
 
+    /// # Description
+    ///
+    /// Reads `filename`, `path`, `media-title` and `duration` with a single round trip.
+    /// The requests are sent back to back, so they all see the same file unless it
+    /// changes while mpv is answering them.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use mpvipc::{Mpv, Error};
+    /// # fn main() -> Result<(), Error> {
+    /// let mpv = Mpv::connect("/tmp/mpvsocket")?;
+    /// let now_playing = mpv.now_playing()?;
+    /// println!("{}", now_playing.title.unwrap_or_default());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn now_playing(&self) -> Result<NowPlaying, Error> {
+        let values =
+            get_mpv_properties::<Value>(self, &["filename", "path", "media-title", "duration"])?;
+        let mut values = values.into_iter().map(Option::unwrap_or_default);
+        let mut next_string = || values.next().and_then(|v| v.as_str().map(str::to_string));
+        let filename = next_string();
+        let path = next_string();
+        let title = next_string();
+        let duration = values.next().and_then(|v| v.as_f64());
+        Ok(NowPlaying {
+            filename,
+            path,
+            title,
+            duration,
+        })
+    }
+
+    /// # Description
+    ///
+    /// Returns the playback position as a fraction between 0.0 and 1.0
+    /// (`percent-pos` / 100), or `None` while nothing is playing.
+    pub fn position_fraction(&self) -> Result<Option<f64>, Error> {
+        let percent = get_mpv_properties::<f64>(self, &["percent-pos"])?.remove(0);
+        Ok(percent.map(|percent| percent / 100f64))
+    }
+
+    /// # Description
+    ///
+    /// Returns `time-pos` formatted as `H:MM:SS`, or as `M:SS` below an hour,
+    /// or `None` while nothing is playing.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use mpvipc::{Mpv, Error};
+    /// # fn main() -> Result<(), Error> {
+    /// let mpv = Mpv::connect("/tmp/mpvsocket")?;
+    /// if let (Some(position), Some(duration)) =
+    ///     (mpv.time_pos_formatted()?, mpv.duration_formatted()?)
+    /// {
+    ///     println!("{} / {}", position, duration);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn time_pos_formatted(&self) -> Result<Option<String>, Error> {
+        let seconds = get_mpv_properties::<f64>(self, &["time-pos"])?.remove(0);
+        Ok(seconds.map(format_time))
+    }
+
+    /// Returns `duration` formatted like [Mpv::time_pos_formatted].
+    pub fn duration_formatted(&self) -> Result<Option<String>, Error> {
+        let seconds = get_mpv_properties::<f64>(self, &["duration"])?.remove(0);
+        Ok(seconds.map(format_time))
+    }
+
+    /// # Description
+    ///
+    /// Asks mpv to report changes of _property_ as [Event::PropertyChange] with the
+    /// given `id`. Returns once mpv registered the observation, which also works
+    /// while mpv is idle and nothing is loaded.
+    ///
+    /// mpv first reports the current value. Properties that only exist while a file
+    /// is loaded, like `duration` or `path`, are reported as unavailable then
+    /// (`None` or `MpvDataType::Null`), and the next change only arrives once a file
+    /// is loaded. Properties of the player itself, like `pause` or `volume`, report
+    /// their value right away.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use mpvipc::{Event, Mpv, Error, Property};
+    /// # fn main() -> Result<(), Error> {
+    /// let mut mpv = Mpv::connect("/tmp/mpvsocket")?;
+    /// mpv.observe_property(1, "duration")?;
+    /// loop {
+    ///     if let Event::PropertyChange { property: Property::Duration(Some(duration)), .. } =
+    ///         mpv.event_listen()?
+    ///     {
+    ///         println!("loaded a file of {} seconds", duration);
+    ///     }
+    /// }
+    /// # }
+    /// ```
     pub fn observe_property(&self, id: isize, property: &str) -> Result<(), Error> {
         self.run_command(MpvCommand::Observe {
-            id: id,
+            id,
             property: property.to_string(),
         })
     }
 
+    /// # Description
+    ///
+    /// Observes _property_ like [Mpv::observe_property], but mpv reports its values
+    /// formatted as strings, the way `get_property_string` returns them. Changes
+    /// are delivered as `Property::Unknown` with `MpvDataType::String` data unless the
+    /// property is a string anyway, so properties whose native shape is awkward to
+    /// decode can be shown as they are. While the property is unavailable the data
+    /// is `MpvDataType::Null`.
+    ///
+    /// The observation is stopped with [Mpv::unobserve_property] like any other.
+    pub fn observe_property_string(&self, id: isize, property: &str) -> Result<(), Error> {
+        observe_mpv_property_string(self, &id, property)
+    }
+
+    /// # Description
+    ///
+    /// Observes all given properties, assigning each one an id that is not used by
+    /// another observation on this connection. Returns the assigned ids together with
+    /// the property names, in the order of `properties`.
+    ///
+    /// If one observation fails, the error is returned and the properties observed
+    /// before it stay observed.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use mpvipc::{Error, Mpv};
+    /// fn main() -> Result<(), Error> {
+    ///     let mpv = Mpv::connect("/tmp/mpvsocket")?;
+    ///     for (id, property) in mpv.observe_properties(&["pause", "volume", "path"])? {
+    ///         println!("observing {} as {}", property, id);
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn observe_properties(&self, properties: &[&str]) -> Result<Vec<(isize, String)>, Error> {
+        let mut observed = Vec::with_capacity(properties.len());
+        for property in properties {
+            let id = self.next_observe_id();
+            self.observe_property(id, property)?;
+            observed.push((id, property.to_string()));
+        }
+        Ok(observed)
+    }
+
+    /// # Description
+    ///
+    /// Stops all observations of this connection, runs `f` and observes the same
+    /// properties with the same ids again, e.g. to avoid a flood of events while
+    /// rebuilding the playlist. Changes made during `f` are not reported, but
+    /// observing again reports the current value of each property.
+    ///
+    /// Events mpv sent before the observations stopped may still be read afterwards.
+    /// Properties observed with [Mpv::observe_property_string] are observed as strings
+    /// again. If stopping an observation fails, `f` is not run and the observations
+    /// stopped until then are started again.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use mpvipc::{Mpv, Error, PlaylistAddOptions, PlaylistAddTypeOptions};
+    /// # fn main() -> Result<(), Error> {
+    /// let mut mpv = Mpv::connect("/tmp/mpvsocket")?;
+    /// mpv.observe_property(1, "playlist")?;
+    /// mpv.with_observations_paused(|mpv| {
+    ///     mpv.playlist_clear()?;
+    ///     for file in ["a.mkv", "b.mkv", "c.mkv"] {
+    ///         mpv.playlist_add(file, PlaylistAddTypeOptions::File, PlaylistAddOptions::Append)?;
+    ///     }
+    ///     Ok(())
+    /// })?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_observations_paused<F, R>(&mut self, f: F) -> Result<R, Error>
+    where
+        F: FnOnce(&mut Mpv) -> Result<R, Error>,
+    {
+        let mut paused: Vec<(isize, Observation)> = self
+            .observations
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .map(|(id, observation)| (*id, observation.clone()))
+            .collect();
+        paused.sort();
+        let mut stopped = 0;
+        let mut result = Ok(());
+        for (id, _) in &paused {
+            result = unobserve_mpv_property(self, id);
+            if result.is_err() {
+                break;
+            }
+            stopped += 1;
+        }
+        let result = result.and_then(|()| f(self));
+        let observed = paused[..stopped]
+            .iter()
+            .try_for_each(|(id, observation)| observe_again(self, *id, observation));
+        let value = result?;
+        observed.map(|()| value)
+    }
+
+    fn next_observe_id(&self) -> isize {
+        let observations = self
+            .observations
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        loop {
+            let id = self.next_observe_id.fetch_add(1, Ordering::Relaxed);
+            if !observations.contains_key(&id) {
+                return id;
+            }
+        }
+    }
+
     pub fn unobserve_property(&self, id: isize) -> Result<(), Error> {
         self.run_command(MpvCommand::Unobserve(id))
     }
@@ -475,8 +2748,8 @@ impl Mpv {
     /// - **args**      a slice of &str's which define the arguments
     ///
     /// # Example
-    /// ```
-    /// use mpvipc::{Mpv, Error};
+    /// ```no_run
+    /// use mpvipc::{Error, Mpv, MpvCommand, SeekOptions};
     /// fn main() -> Result<(), Error> {
     ///     let mpv = Mpv::connect("/tmp/mpvsocket")?;
     ///
@@ -492,10 +2765,50 @@ impl Mpv {
     /// }
     /// ```
     pub fn run_command(&self, command: MpvCommand) -> Result<(), Error> {
+        self.run_command_ret(command).map(|_| ())
+    }
+
+    /// # Description
+    ///
+    /// Works like [Mpv::run_command], but returns the `data` of mpv's reply.
+    /// Commands that don't return anything yield `Value::Null`.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use mpvipc::{Error, Mpv, MpvCommand};
+    /// # fn main() -> Result<(), Error> {
+    /// let mpv = Mpv::connect("/tmp/mpvsocket")?;
+    /// let reply = mpv.run_command_ret(MpvCommand::PlaylistShuffle)?;
+    /// assert!(reply.is_null());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn run_command_ret(&self, command: MpvCommand) -> Result<Value, Error> {
         match command {
-            MpvCommand::LoadFile { file, option } => run_mpv_command(
+            MpvCommand::DefineSection {
+                name,
+                contents,
+                flags,
+            } => {
+                let mut args = vec![name.as_str(), contents.as_str()];
+                if let Some(ref flags) = flags {
+                    args.push(flags);
+                }
+                run_mpv_command_ret(self, &["define-section"], &args)
+            }
+            MpvCommand::DisableSection(name) => {
+                run_mpv_command_ret(self, &["disable-section"], &[&name])
+            }
+            MpvCommand::EnableSection { name, flags } => {
+                let mut args = vec![name.as_str()];
+                if let Some(ref flags) = flags {
+                    args.push(flags);
+                }
+                run_mpv_command_ret(self, &["enable-section"], &args)
+            }
+            MpvCommand::LoadFile { file, option } => run_mpv_command_ret(
                 self,
-                "loadfile",
+                &["loadfile"],
                 &[
                     file.as_ref(),
                     match option {
@@ -504,9 +2817,9 @@ impl Mpv {
                     },
                 ],
             ),
-            MpvCommand::LoadList { file, option } => run_mpv_command(
+            MpvCommand::LoadList { file, option } => run_mpv_command_ret(
                 self,
-                "loadlist",
+                &["loadlist"],
                 &[
                     file.as_ref(),
                     match option {
@@ -515,33 +2828,33 @@ impl Mpv {
                     },
                 ],
             ),
-            MpvCommand::Observe { id, property } => observe_mpv_property(self, &id, &property),
-            MpvCommand::PlaylistClear => run_mpv_command(self, "playlist-clear", &[]),
-            MpvCommand::PlaylistMove { from, to } => {
-                run_mpv_command(self, "playlist-move", &[&from.to_string(), &to.to_string()])
+            MpvCommand::Observe { id, property } => {
+                observe_mpv_property(self, &id, &property).map(|()| Value::Null)
             }
-            MpvCommand::PlaylistNext => run_mpv_command(self, "playlist-next", &[]),
-            MpvCommand::PlaylistPrev => run_mpv_command(self, "playlist-prev", &[]),
+            MpvCommand::PlaylistClear => run_mpv_command_ret(self, &["playlist-clear"], &[]),
+            MpvCommand::PlaylistMove { from, to } => run_mpv_command_ret(
+                self,
+                &["playlist-move"],
+                &[&from.to_string(), &to.to_string()],
+            ),
+            MpvCommand::PlaylistNext => run_mpv_command_ret(self, &["playlist-next"], &[]),
+            MpvCommand::PlaylistPrev => run_mpv_command_ret(self, &["playlist-prev"], &[]),
             MpvCommand::PlaylistRemove(id) => {
-                run_mpv_command(self, "playlist-remove", &[&id.to_string()])
-            }
-            MpvCommand::PlaylistShuffle => run_mpv_command(self, "playlist-shuffle", &[]),
-            MpvCommand::Quit => run_mpv_command(self, "quit", &[]),
-            MpvCommand::ScriptMessage(args) => {
-                let str_args: Vec<_> = args.iter().map(String::as_str).collect();
-                run_mpv_command(self, "script-message", &str_args)
+                run_mpv_command_ret(self, &["playlist-remove"], &[&id.to_string()])
             }
-            MpvCommand::ScriptMessageTo { target, args } => {
-                let mut cmd_args: Vec<_> = vec![target.as_str()];
-                let mut str_args: Vec<_> = args.iter().map(String::as_str).collect();
-                cmd_args.append(&mut str_args);
-                run_mpv_command(self, "script-message-to", &cmd_args)
+            MpvCommand::PlaylistShuffle => run_mpv_command_ret(self, &["playlist-shuffle"], &[]),
+            MpvCommand::Quit => run_mpv_command_ret(self, &["quit"], &[]),
+            MpvCommand::ScriptMessage(args) => self.run_command_owned_ret("script-message", args),
+            MpvCommand::ScriptMessageTo { target, mut args } => {
+                args.insert(0, target);
+                self.run_command_owned_ret("script-message-to", args)
             }
-            MpvCommand::Seek { seconds, option } => run_mpv_command(
+            MpvCommand::Seek { seconds, option } => run_mpv_command_ret(
                 self,
-                "seek",
+                &["seek"],
                 &[
-                    &seconds.to_string(),
+                    // fixed precision keeps the argument a plain decimal number
+                    &format!("{:.6}", finite(seconds)?),
                     match option {
                         SeekOptions::Absolute => "absolute",
                         SeekOptions::Relative => "relative",
@@ -550,25 +2863,53 @@ impl Mpv {
                     },
                 ],
             ),
-            MpvCommand::Stop => run_mpv_command(self, "stop", &[]),
-            MpvCommand::Unobserve(id) => unobserve_mpv_property(self, &id),
+            MpvCommand::Stop => run_mpv_command_ret(self, &["stop"], &[]),
+            MpvCommand::Unobserve(id) => unobserve_mpv_property(self, &id).map(|()| Value::Null),
             MpvCommand::ShowText { text, duration_ms, level }=> {
                 let mut args = vec![text, duration_ms.unwrap_or(-1).to_string()];
                 if let Some(level) = level {
                     args.push(level.to_string());
                 }
-                let str_args: Vec<_> = args.iter().map(String::as_str).collect();
-                run_mpv_command(self, "show-text", &str_args)
+                self.run_command_owned_ret("show-text", args)
             },
         }
     }
 
+    /// Works like [Mpv::run_command], but waits at most `timeout` for the replies
+    /// instead of the timeout set with [Mpv::set_timeout].
+    pub fn run_command_timeout(&self, command: MpvCommand, timeout: Duration) -> Result<(), Error> {
+        with_call_timeout(timeout, || self.run_command(command))
+    }
+
     /// Run a custom command.
     /// This should only be used if the desired command is not implemented
     /// with [MpvCommand].
     pub fn run_command_raw(&self, command: &str, args: &[&str]) -> Result<(), Error> {
         run_mpv_command(self, command, args)
     }
+
+    /// Works like [Mpv::run_command_raw], but takes the arguments as owned strings,
+    /// e.g. when they are computed and collected into a `Vec` anyway.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use mpvipc::{Mpv, Error};
+    /// # fn main() -> Result<(), Error> {
+    /// let mpv = Mpv::connect("/tmp/mpvsocket")?;
+    /// let volume = 50;
+    /// mpv.run_command_owned("set", vec!["volume".to_string(), volume.to_string()])?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn run_command_owned(&self, command: &str, args: Vec<String>) -> Result<(), Error> {
+        self.run_command_owned_ret(command, args).map(|_| ())
+    }
+
+    fn run_command_owned_ret(&self, command: &str, args: Vec<String>) -> Result<Value, Error> {
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        run_mpv_command_ret(self, &[command], &args)
+    }
+
     /// Run a custom command.
     /// This should only be used if the desired command is not implemented
     /// with [MpvCommand].
@@ -577,33 +2918,245 @@ impl Mpv {
         run_mpv_command2(self, command, args)
     }
 
-    pub fn playlist_add(
-        &self,
+    /// # Description
+    ///
+    /// Sends `command` as a raw request and returns mpv's reply object, e.g.
+    /// `{"error": "success", "data": ..., "request_id": 3}`. A `request_id` is added
+    /// to the request so the reply can be told apart from events and other replies.
+    ///
+    /// Unlike the other methods, an error reported by mpv is not turned into an
+    /// `Err`; check the `error` field of the reply instead.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use mpvipc::{Mpv, Error};
+    /// use serde_json::json;
+    /// fn main() -> Result<(), Error> {
+    ///     let mpv = Mpv::connect("/tmp/mpvsocket")?;
+    ///     let reply = mpv.raw_request(&[json!("get_property"), json!("volume")])?;
+    ///     println!("{} {}", reply["error"], reply["data"]);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn raw_request(&self, command: &[Value]) -> Result<Value, Error> {
+        send_mpv_request(self, command)
+    }
+
+    /// Adds `file` to the playlist. mpv accepts the command before it tries to open
+    /// the file, so a missing or unplayable file is not reported here but by a later
+    /// `end-file` event with reason `error`. Use [Mpv::load_file_and_wait] to find out
+    /// whether the file could be opened.
+    pub fn playlist_add(
+        &self,
+        file: &str,
+        file_type: PlaylistAddTypeOptions,
+        option: PlaylistAddOptions,
+    ) -> Result<(), Error> {
+        match file_type {
+            PlaylistAddTypeOptions::File => self.run_command(MpvCommand::LoadFile {
+                file: file.to_string(),
+                option,
+            }),
+
+            PlaylistAddTypeOptions::Playlist => self.run_command(MpvCommand::LoadList {
+                file: file.to_string(),
+                option,
+            }),
+        }
+    }
+
+    /// # Description
+    ///
+    /// Adds `file` like [Mpv::playlist_add], unless an entry with exactly this
+    /// filename is already in the playlist. Returns whether the file was added.
+    ///
+    /// Filenames are compared as strings the way they were added, so e.g. a relative
+    /// and an absolute path to the same file, or a URL with and without
+    /// percent-encoding, count as different entries.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use mpvipc::{Mpv, Error, PlaylistAddOptions};
+    /// # fn main() -> Result<(), Error> {
+    /// let mpv = Mpv::connect("/tmp/mpvsocket")?;
+    /// if !mpv.playlist_add_unique("song.flac", PlaylistAddOptions::Append)? {
+    ///     println!("song.flac is already queued");
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn playlist_add_unique(
+        &self,
+        file: &str,
+        option: PlaylistAddOptions,
+    ) -> Result<bool, Error> {
+        let Playlist(entries) = self.get_playlist()?;
+        if entries.iter().any(|entry| entry.filename == file) {
+            return Ok(false);
+        }
+        self.playlist_add(file, PlaylistAddTypeOptions::File, option)?;
+        Ok(true)
+    }
+
+    /// # Description
+    ///
+    /// Loads `file` like [Mpv::playlist_add] and waits until mpv opened it. Returns
+    /// the `file_error` of mpv's `end-file` event as `ErrorCode::MpvError` if the
+    /// file could not be opened, e.g. `loading failed`.
+    ///
+    /// With [PlaylistAddOptions::Append] the file only starts if nothing else is
+    /// playing, otherwise this waits until `timeout` and returns
+    /// `ErrorCode::Timeout`. The timeout is not applied on Windows. Events read
+    /// meanwhile are dropped.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use mpvipc::{Mpv, Error, PlaylistAddOptions};
+    /// # use std::time::Duration;
+    /// # fn main() -> Result<(), Error> {
+    /// let mut mpv = Mpv::connect("/tmp/mpvsocket")?;
+    /// let timeout = Some(Duration::from_secs(5));
+    /// if let Err(error) = mpv.load_file_and_wait("movie.mkv", PlaylistAddOptions::Replace, timeout) {
+    ///     eprintln!("could not play movie.mkv: {}", error);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn load_file_and_wait(
+        &mut self,
         file: &str,
-        file_type: PlaylistAddTypeOptions,
         option: PlaylistAddOptions,
+        timeout: Option<Duration>,
     ) -> Result<(), Error> {
-        match file_type {
-            PlaylistAddTypeOptions::File => self.run_command(MpvCommand::LoadFile {
-                file: file.to_string(),
-                option,
-            }),
+        let option = match option {
+            PlaylistAddOptions::Append => "append",
+            PlaylistAddOptions::Replace => "replace",
+        };
+        load_file_and_wait(self, file, option, timeout)
+    }
 
-            PlaylistAddTypeOptions::Playlist => self.run_command(MpvCommand::LoadList {
-                file: file.to_string(),
-                option,
-            }),
-        }
+    /// # Description
+    ///
+    /// Replaces the playlist with `file`, unpauses and waits until the file stopped
+    /// playing. Returns why it stopped, e.g. [EndFileReason::Eof] if it played to the
+    /// end or [EndFileReason::Error] if it could not be opened.
+    ///
+    /// Returns `ErrorCode::Timeout` if the file is still playing after `timeout`;
+    /// the timeout is not applied on Windows. Events read meanwhile are dropped.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use mpvipc::{EndFileReason, Mpv, Error};
+    /// # fn main() -> Result<(), Error> {
+    /// let mut mpv = Mpv::connect("/tmp/mpvsocket")?;
+    /// for file in ["a.mkv", "b.mkv"] {
+    ///     if let EndFileReason::Error(error) = mpv.play_to_end(file, None)? {
+    ///         eprintln!("{}: {}", file, error);
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn play_to_end(
+        &mut self,
+        file: &str,
+        timeout: Option<Duration>,
+    ) -> Result<EndFileReason, Error> {
+        play_to_end(self, file, timeout)
     }
 
     pub fn playlist_clear(&self) -> Result<(), Error> {
         self.run_command(MpvCommand::PlaylistClear)
     }
 
+    /// Returns the number of entries in the playlist.
+    pub fn playlist_count(&self) -> Result<usize, Error> {
+        get_mpv_property::<usize>(self, "playlist-count")
+    }
+
+    /// Returns the index of the current playlist entry, or `None` if no entry
+    /// is selected (mpv reports `-1` while idle).
+    pub fn playlist_pos(&self) -> Result<Option<i64>, Error> {
+        let pos = get_mpv_property::<i64>(self, "playlist-pos")?;
+        Ok(if pos < 0 { None } else { Some(pos) })
+    }
+
+    /// # Description
+    ///
+    /// Returns the 0-based index of the current playlist entry (`playlist-pos`), or
+    /// `None` if no entry is selected.
+    ///
+    /// mpv also has `playlist-pos-1`, which counts from 1 and reports 0 instead of
+    /// -1 when no entry is selected. Reading one while expecting the other is off by
+    /// one without any error, so pick the helper matching how the index is used:
+    /// this one for indices into [Mpv::get_playlist], [Mpv::playlist_pos_one_based]
+    /// for showing the position to users.
+    pub fn playlist_pos_zero_based(&self) -> Result<Option<usize>, Error> {
+        Ok(self.playlist_pos()?.map(|pos| pos as usize))
+    }
+
+    /// Returns the 1-based position of the current playlist entry
+    /// (`playlist-pos-1`), or `None` if no entry is selected. See
+    /// [Mpv::playlist_pos_zero_based].
+    pub fn playlist_pos_one_based(&self) -> Result<Option<usize>, Error> {
+        let pos = get_mpv_property::<i64>(self, "playlist-pos-1")?;
+        Ok(if pos < 1 { None } else { Some(pos as usize) })
+    }
+
+    /// # Description
+    ///
+    /// Returns whether [Mpv::next] would move to another entry.
+    /// With `loop-playlist` enabled, the last entry wraps around to the first, so
+    /// this is true as long as an entry is selected.
+    pub fn can_go_next(&self) -> Result<bool, Error> {
+        let pos = match self.playlist_pos()? {
+            Some(pos) => pos,
+            None => return Ok(false),
+        };
+        if pos + 1 < self.playlist_count()? as i64 {
+            return Ok(true);
+        }
+        get_mpv_switch(self, "loop-playlist")
+    }
+
+    /// # Description
+    ///
+    /// Returns whether [Mpv::prev] would move to another entry.
+    /// With `loop-playlist` enabled, the first entry wraps around to the last, so
+    /// this is true as long as an entry is selected.
+    pub fn can_go_prev(&self) -> Result<bool, Error> {
+        let pos = match self.playlist_pos()? {
+            Some(pos) => pos,
+            None => return Ok(false),
+        };
+        if pos > 0 {
+            return Ok(true);
+        }
+        get_mpv_switch(self, "loop-playlist")
+    }
+
     pub fn playlist_move_id(&self, from: usize, to: usize) -> Result<(), Error> {
         self.run_command(MpvCommand::PlaylistMove { from, to })
     }
 
+    /// # Description
+    ///
+    /// Moves the playlist entry with the given `playlist_entry_id` to `to_index`.
+    /// The current index of the entry is looked up first, so this keeps working
+    /// after the playlist has been reordered.
+    ///
+    /// Returns `ErrorCode::MissingValue` if no entry with that id exists anymore.
+    pub fn playlist_move_entry(&self, id: usize, to_index: usize) -> Result<(), Error> {
+        let Playlist(entries) = self.get_playlist()?;
+        match entries
+            .iter()
+            .find(|entry| entry.playlist_entry_id == Some(id))
+        {
+            Some(entry) => self.playlist_move_id(entry.id, to_index),
+            None => Err(Error(ErrorCode::MissingValue)),
+        }
+    }
+
     pub fn playlist_play_id(&self, id: usize) -> Result<(), Error> {
         set_mpv_property(self, "playlist-pos", json!(id))
     }
@@ -626,26 +3179,158 @@ impl Mpv {
         self.run_command(MpvCommand::PlaylistShuffle)
     }
 
+    /// Returns `ErrorCode::UnexpectedValue` if `seconds` is `NaN` or infinite.
     pub fn seek(&self, seconds: f64, option: SeekOptions) -> Result<(), Error> {
         self.run_command(MpvCommand::Seek { seconds, option })
     }
 
+    /// # Description
+    ///
+    /// Jumps `delta` chapters forward (or backward if negative) using `add chapter`.
+    ///
+    /// Returns `ErrorCode::MissingValue` if the current file has no chapters.
+    pub fn seek_chapter(&self, delta: i64) -> Result<(), Error> {
+        if get_mpv_property::<usize>(self, "chapters")? == 0 {
+            return Err(Error(ErrorCode::MissingValue));
+        }
+        run_mpv_command(self, "add", &["chapter", &delta.to_string()])
+    }
+
+    /// # Description
+    ///
+    /// Jumps to the chapter with the given index by setting the `chapter` property.
+    ///
+    /// Returns `ErrorCode::MissingValue` if the current file has no chapters.
+    pub fn seek_to_chapter(&self, index: i64) -> Result<(), Error> {
+        if get_mpv_property::<usize>(self, "chapters")? == 0 {
+            return Err(Error(ErrorCode::MissingValue));
+        }
+        set_mpv_property(self, "chapter", json!(index))
+    }
+
+    /// # Description
+    ///
+    /// Sets _<property>_ to `value` and waits until mpv reports the new value, e.g.
+    /// because `pause` only settles after the set command returned. The property is
+    /// observed for the duration of the call, events read meanwhile are dropped.
+    ///
+    /// `value` should be given the way mpv reports it (e.g. `"inf"` rather than
+    /// `true` for `loop-file`), otherwise the confirmation is never recognized.
+    /// Returns `ErrorCode::Timeout` if no confirmation arrived within `timeout`;
+    /// the timeout is not applied on Windows.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use mpvipc::{Mpv, Error};
+    /// # use serde_json::json;
+    /// # use std::time::Duration;
+    /// # fn main() -> Result<(), Error> {
+    /// let mut mpv = Mpv::connect("/tmp/mpvsocket")?;
+    /// mpv.set_and_confirm("pause", json!(true), Some(Duration::from_secs(1)))?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_and_confirm(
+        &mut self,
+        property: &str,
+        value: Value,
+        timeout: Option<Duration>,
+    ) -> Result<(), Error> {
+        let id = self.next_observe_id();
+        observe_mpv_property(self, &id, property)?;
+        let confirmed = set_mpv_property_confirmed(self, id, property, value, timeout);
+        let unobserved = unobserve_mpv_property(self, &id);
+        confirmed.and(unobserved)
+    }
+
+    /// # Description
+    ///
+    /// Reads the parameters of the current video (`video-params`) together with
+    /// `container-fps`. Returns [VideoParams::default] if there is no video.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use mpvipc::{Mpv, Error};
+    /// # fn main() -> Result<(), Error> {
+    /// let mpv = Mpv::connect("/tmp/mpvsocket")?;
+    /// let video = mpv.video_params()?;
+    /// if let (Some(width), Some(height)) = (video.width, video.height) {
+    ///     println!("{}x{}", width, height);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn video_params(&self) -> Result<VideoParams, Error> {
+        let mut values = get_mpv_properties::<Value>(self, &["video-params", "container-fps"])?
+            .into_iter()
+            .map(Option::unwrap_or_default);
+        let params = values.next().unwrap_or_default();
+        let fps = values.next().and_then(|fps| fps.as_f64());
+        Ok(VideoParams {
+            width: params["w"].as_u64().map(|n| n as usize),
+            height: params["h"].as_u64().map(|n| n as usize),
+            pixelformat: params["pixelformat"].as_str().map(str::to_string),
+            aspect: params["aspect"].as_f64(),
+            rotate: params["rotate"].as_u64().map(|n| n as usize),
+            fps,
+        })
+    }
+
+    /// # Description
+    ///
+    /// Reads the size of the OSD and the margins of the video in it
+    /// (`osd-dimensions`), e.g. to position ASS overlays.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use mpvipc::{Mpv, Error};
+    /// # fn main() -> Result<(), Error> {
+    /// let mpv = Mpv::connect("/tmp/mpvsocket")?;
+    /// let osd = mpv.osd_dimensions()?;
+    /// println!("{:?}x{:?}", osd.width, osd.height);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn osd_dimensions(&self) -> Result<OsdDimensions, Error> {
+        let dimensions = get_mpv_properties::<Value>(self, &["osd-dimensions"])?
+            .remove(0)
+            .unwrap_or_default();
+        let pixels = |key: &str| dimensions[key].as_u64().map(|n| n as usize);
+        Ok(OsdDimensions {
+            width: pixels("w"),
+            height: pixels("h"),
+            par: dimensions["par"].as_f64(),
+            aspect: dimensions["aspect"].as_f64(),
+            margin_top: pixels("mt"),
+            margin_bottom: pixels("mb"),
+            margin_left: pixels("ml"),
+            margin_right: pixels("mr"),
+        })
+    }
+
+    /// Returns the refresh rate mpv assumes for the display (`display-fps`), or `None`
+    /// if it is unknown.
+    pub fn display_fps(&self) -> Result<Option<f64>, Error> {
+        Ok(get_mpv_properties::<f64>(self, &["display-fps"])?.remove(0))
+    }
+
+    /// Returns the refresh rate mpv measured for the display
+    /// (`estimated-display-fps`), or `None` if it is unknown.
+    pub fn estimated_display_fps(&self) -> Result<Option<f64>, Error> {
+        Ok(get_mpv_properties::<f64>(self, &["estimated-display-fps"])?.remove(0))
+    }
+
+    /// Switches to the edition with the given index (see [Mpv::get_edition_list]).
+    pub fn set_edition(&self, index: i64) -> Result<(), Error> {
+        set_mpv_property(self, "edition", json!(index))
+    }
+
     pub fn set_loop_file(&self, option: Switch) -> Result<(), Error> {
         let mut enabled = false;
         match option {
             Switch::On => enabled = true,
             Switch::Off => {}
-            Switch::Toggle => match get_mpv_property_string(self, "loop-file") {
-                Ok(value) => match value.as_ref() {
-                    "false" => {
-                        enabled = true;
-                    }
-                    _ => {
-                        enabled = false;
-                    }
-                },
-                Err(msg) => return Err(msg),
-            },
+            Switch::Toggle => enabled = !get_mpv_switch(self, "loop-file")?,
         }
         set_mpv_property(self, "loop-file", json!(enabled))
     }
@@ -655,17 +3340,7 @@ impl Mpv {
         match option {
             Switch::On => enabled = true,
             Switch::Off => {}
-            Switch::Toggle => match get_mpv_property_string(self, "loop-playlist") {
-                Ok(value) => match value.as_ref() {
-                    "false" => {
-                        enabled = true;
-                    }
-                    _ => {
-                        enabled = false;
-                    }
-                },
-                Err(msg) => return Err(msg),
-            },
+            Switch::Toggle => enabled = !get_mpv_switch(self, "loop-playlist")?,
         }
         set_mpv_property(self, "loop-playlist", json!(enabled))
     }
@@ -685,27 +3360,48 @@ impl Mpv {
         set_mpv_property(self, "mute", json!(enabled))
     }
 
+    /// Works like [Mpv::set_property] for one of the [KnownProperty] variants.
+    pub fn set<T: SetPropertyTypeHandler<T>>(
+        &self,
+        property: KnownProperty,
+        value: T,
+    ) -> Result<(), Error> {
+        T::set_property_generic(self, property.as_str(), value)
+    }
+
     /// # Description
     ///
     /// Sets the mpv property _<property>_ to _<value>_.
     ///
     /// ## Supported types
     /// - String
+    /// - &str
     /// - bool
     /// - f64
+    /// - f32
     /// - usize
+    /// - i64
+    /// - i32
+    /// - u32
     ///
     /// ## Input arguments
     ///
     /// - **property** defines the mpv property that should be retrieved
     /// - **value** defines the value of the given mpv property _<property>_
     ///
+    /// If mpv rejects the value, the `ErrorCode::MpvError` names the property and
+    /// the value along with mpv's message, e.g.
+    /// `setting 'volume'=9999: error running command`. `NaN` and infinite numbers are
+    /// not sent at all and result in `ErrorCode::UnexpectedValue`.
+    ///
     /// # Example
-    /// ```
+    /// ```no_run
     /// use mpvipc::{Mpv, Error};
     /// fn main() -> Result<(), Error> {
     ///     let mpv = Mpv::connect("/tmp/mpvsocket")?;
     ///     mpv.set_property("pause", true)?;
+    ///     mpv.set_property("volume", 80)?;
+    ///     mpv.set_property("title", "mpv")?;
     ///     Ok(())
     /// }
     /// ```
@@ -717,7 +3413,58 @@ impl Mpv {
         T::set_property_generic(self, property, value)
     }
 
+    /// # Description
+    ///
+    /// Sets the mpv property _<property>_ to the string _<value>_. This is the
+    /// counterpart to [Mpv::get_property_string] and useful for properties that take
+    /// a keyword, e.g. `no` for `ab-loop-a` or `auto` for `aid`.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use mpvipc::{Mpv, Error};
+    /// fn main() -> Result<(), Error> {
+    ///     let mpv = Mpv::connect("/tmp/mpvsocket")?;
+    ///     mpv.set_property_string("aid", "auto")?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn set_property_string(&self, property: &str, value: &str) -> Result<(), Error> {
+        set_mpv_property(self, property, json!(value))
+    }
+
+    /// # Description
+    ///
+    /// Sets each option to its value given as a string, the way it would be written
+    /// on the command line. The options are set through their `options/<name>`
+    /// properties, which also covers options that have no property of their own.
+    ///
+    /// Stops at the first option mpv rejects; its `ErrorCode::MpvError` names the
+    /// option and value, e.g. `setting 'options/cache'="maybe": ...`. The options
+    /// before it stay set.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use mpvipc::{Mpv, Error};
+    /// # fn main() -> Result<(), Error> {
+    /// let mpv = Mpv::connect("/tmp/mpvsocket")?;
+    /// mpv.apply_options(&[
+    ///     ("sub-font-size", "42"),
+    ///     ("cache", "yes"),
+    ///     ("demuxer-max-bytes", "500MiB"),
+    /// ])?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn apply_options(&self, options: &[(&str, &str)]) -> Result<(), Error> {
+        for (name, value) in options {
+            set_mpv_property(self, &format!("options/{}", name), json!(value))?;
+        }
+        Ok(())
+    }
+
+    /// Returns `ErrorCode::UnexpectedValue` if `input_speed` is `NaN` or infinite.
     pub fn set_speed(&self, input_speed: f64, option: NumberChangeOptions) -> Result<(), Error> {
+        let input_speed = finite(input_speed)?;
         match get_mpv_property::<f64>(self, "speed") {
             Ok(speed) => match option {
                 NumberChangeOptions::Increase => {
@@ -736,7 +3483,22 @@ impl Mpv {
         }
     }
 
+    /// # Description
+    ///
+    /// Sets how long commands wait for mpv's reply before failing with
+    /// `ErrorCode::Timeout`, or `None` to wait indefinitely (the default).
+    /// Clones start with the timeout of the connection they were cloned from.
+    ///
+    /// [Mpv::event_listen] is not affected and always waits for the next event.
+    /// On Windows the timeout only applies after [Mpv::into_event_thread], as named
+    /// pipes have no read timeout.
+    pub fn set_timeout(&self, timeout: Option<Duration>) {
+        *self.timeout.lock().unwrap_or_else(PoisonError::into_inner) = timeout;
+    }
+
+    /// Returns `ErrorCode::UnexpectedValue` if `input_volume` is `NaN` or infinite.
     pub fn set_volume(&self, input_volume: f64, option: NumberChangeOptions) -> Result<(), Error> {
+        let input_volume = finite(input_volume)?;
         match get_mpv_property::<f64>(self, "volume") {
             Ok(volume) => match option {
                 NumberChangeOptions::Increase => {
@@ -755,6 +3517,57 @@ impl Mpv {
         }
     }
 
+    /// # Description
+    ///
+    /// Works like [Mpv::set_volume], but keeps the resulting volume between 0 and the
+    /// current `volume-max`. mpv accepts any volume up to `volume-max` (130 by
+    /// default), so this is useful when everything above 100 should be reachable
+    /// only deliberately, e.g. by lowering `volume-max` to 100.
+    pub fn set_volume_clamped(
+        &self,
+        input_volume: f64,
+        option: NumberChangeOptions,
+    ) -> Result<(), Error> {
+        let input_volume = finite(input_volume)?;
+        let volume = match option {
+            NumberChangeOptions::Increase => {
+                get_mpv_property::<f64>(self, "volume")? + input_volume
+            }
+            NumberChangeOptions::Decrease => {
+                get_mpv_property::<f64>(self, "volume")? - input_volume
+            }
+            NumberChangeOptions::Absolute => input_volume,
+        };
+        let volume_max = get_mpv_property::<f64>(self, "volume-max")?;
+        set_mpv_property(self, "volume", json!(volume.clamp(0f64, volume_max)))
+    }
+
+    /// # Description
+    ///
+    /// Sets the system volume of the audio output (`ao-volume`) in percent.
+    /// Not every audio output supports this, in which case mpv reports an error.
+    pub fn set_ao_volume(&self, volume: f64) -> Result<(), Error> {
+        set_mpv_property(self, "ao-volume", json!(finite(volume)?))
+    }
+
+    /// Shows `text` on the OSD for mpv's default duration (`osd-duration`).
+    pub fn show_text(&self, text: &str) -> Result<(), Error> {
+        self.run_command(MpvCommand::ShowText {
+            text: text.to_string(),
+            duration_ms: None,
+            level: None,
+        })
+    }
+
+    /// Shows `text` on the OSD for the given duration.
+    pub fn show_text_for(&self, text: &str, duration: Duration) -> Result<(), Error> {
+        self.run_command(MpvCommand::ShowText {
+            text: text.to_string(),
+            duration_ms: Some(i32::try_from(duration.as_millis()).unwrap_or(i32::MAX)),
+            level: None,
+        })
+    }
+
     pub fn stop(&self) -> Result<(), Error> {
         self.run_command(MpvCommand::Stop)
     }
@@ -762,4 +3575,594 @@ impl Mpv {
     pub fn toggle(&self) -> Result<(), Error> {
         run_mpv_command(self, "cycle", &["pause"])
     }
+
+    /// # Description
+    ///
+    /// Toggles playback like [Mpv::toggle] and returns the resulting `pause` state.
+    /// The toggle and the read are sent together and mpv handles the commands of one
+    /// client in order, so the returned value is the state right after the toggle.
+    pub fn toggle_pause(&self) -> Result<bool, Error> {
+        let commands = [
+            vec![json!("cycle"), json!("pause")],
+            vec![json!("get_property"), json!("pause")],
+        ];
+        let [cycled, pause]: [Value; 2] = send_mpv_requests(self, &commands)?
+            .try_into()
+            .map_err(|_| Error(ErrorCode::UnexpectedResult))?;
+        match cycled["error"].as_str() {
+            Some("success") => bool::get_value(pause),
+            Some(error) => Err(Error(ErrorCode::MpvError(error.to_string()))),
+            None => Err(Error(ErrorCode::UnexpectedResult)),
+        }
+    }
+
+    /// # Description
+    ///
+    /// Raises the volume by `step` with mpv's `add volume` command, which stops at
+    /// `volume-max` by itself. Unlike [Mpv::set_volume] this needs no separate read,
+    /// so repeated key presses can't overwrite each other.
+    ///
+    /// Returns `ErrorCode::UnexpectedValue` if `step` is `NaN` or infinite.
+    pub fn volume_up(&self, step: f64) -> Result<(), Error> {
+        run_mpv_command(self, "add", &["volume", &finite(step)?.to_string()])
+    }
+
+    /// Lowers the volume by `step` like [Mpv::volume_up], stopping at 0.
+    pub fn volume_down(&self, step: f64) -> Result<(), Error> {
+        run_mpv_command(self, "add", &["volume", &(-finite(step)?).to_string()])
+    }
+}
+
+#[cfg(all(test, unix))]
+pub(crate) mod socket_tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read};
+    use std::os::fd::IntoRawFd;
+    use std::os::unix::net::UnixStream;
+    use std::thread::JoinHandle;
+
+    /// Returns a connection and the socket on mpv's side of it.
+    pub(crate) fn connected_pair() -> (Mpv, UnixStream) {
+        let (ours, peer) = UnixStream::pair().unwrap();
+        let mpv = unsafe { Mpv::from_raw_fd(ours.into_raw_fd(), "test".to_string()) }.unwrap();
+        (mpv, peer)
+    }
+
+    /// Plays mpv on `peer`: each request is answered with the lines `reply` returns
+    /// for it. Lines that are not events get the `request_id` of the request.
+    pub(crate) fn serve(
+        peer: UnixStream,
+        mut reply: impl FnMut(&Value) -> Vec<Value> + Send + 'static,
+    ) -> JoinHandle<()> {
+        thread::spawn(move || {
+            let mut writer = peer.try_clone().unwrap();
+            for line in BufReader::new(peer).lines() {
+                let Ok(line) = line else { return };
+                // e.g. the rest of a line cut off by a failed write
+                let Ok(request) = serde_json::from_str::<Value>(&line) else {
+                    continue;
+                };
+                for mut response in reply(&request) {
+                    if response.get("event").is_none() && response.get("request_id").is_none() {
+                        response["request_id"] = request["request_id"].clone();
+                    }
+                    if writeln!(writer, "{}", response).is_err() {
+                        return;
+                    }
+                }
+            }
+        })
+    }
+
+    /// Answers every request on `peer` with success and records its `command`.
+    pub(crate) fn record(peer: UnixStream) -> (Arc<Mutex<Vec<Value>>>, JoinHandle<()>) {
+        let commands = Arc::new(Mutex::new(Vec::new()));
+        let recorded = commands.clone();
+        let server = serve(peer, move |request| {
+            recorded.lock().unwrap().push(request["command"].clone());
+            vec![json!({"error": "success"})]
+        });
+        (commands, server)
+    }
+
+    #[test]
+    fn reconnect_restores_observations_the_way_they_were_made() {
+        let path = std::env::temp_dir().join(format!("mpvipc-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let listener = std::os::unix::net::UnixListener::bind(&path).unwrap();
+        let mut mpv = Mpv::connect(path.to_str().unwrap()).unwrap();
+
+        let first = listener.accept().unwrap().0;
+        let (_, server) = record(first.try_clone().unwrap());
+        mpv.observe_property(1, "pause").unwrap();
+        mpv.observe_property_string(2, "volume").unwrap();
+        mpv.observe_property(3, "path").unwrap();
+        mpv.unobserve_property(1).unwrap();
+        // mpv quits and is started again
+        first.shutdown(std::net::Shutdown::Both).unwrap();
+        server.join().unwrap();
+        assert!(matches!(
+            mpv.event_listen(),
+            Err(Error(ErrorCode::Disconnected))
+        ));
+
+        let second = thread::spawn(move || record(listener.accept().unwrap().0));
+        mpv.reconnect().unwrap();
+        let (restored, server) = second.join().unwrap();
+        assert_eq!(
+            *restored.lock().unwrap(),
+            [
+                json!(["observe_property_string", 2, "volume"]),
+                json!(["observe_property", 3, "path"]),
+            ]
+        );
+        drop(mpv);
+        server.join().unwrap();
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn with_observations_paused_restores_string_observations() {
+        let (mut mpv, peer) = connected_pair();
+        let (commands, server) = record(peer);
+        mpv.observe_property(1, "pause").unwrap();
+        mpv.observe_property_string(2, "volume").unwrap();
+        commands.lock().unwrap().clear();
+
+        mpv.with_observations_paused(|mpv| mpv.playlist_clear())
+            .unwrap();
+        assert_eq!(
+            *commands.lock().unwrap(),
+            [
+                json!(["unobserve_property", 1]),
+                json!(["unobserve_property", 2]),
+                json!(["playlist-clear"]),
+                json!(["observe_property", 1, "pause"]),
+                json!(["observe_property_string", 2, "volume"]),
+            ]
+        );
+        drop(mpv);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn script_message_args_round_trip() {
+        let (mut mpv, peer) = connected_pair();
+        // mpv hands script-message arguments to the clients as a client-message
+        let server = serve(peer, |request| {
+            let args = request["command"].as_array().unwrap()[1..].to_vec();
+            vec![
+                json!({"error": "success"}),
+                json!({"event": "client-message", "args": args}),
+            ]
+        });
+        let args = vec![
+            "say \"hi\"".to_string(),
+            "two\nlines".to_string(),
+            "tab\tseparated".to_string(),
+            "ünïcödé ✓ 日本語 🎬".to_string(),
+            "back\\slash".to_string(),
+        ];
+        mpv.run_command(MpvCommand::ScriptMessage(args.clone()))
+            .unwrap();
+        match mpv.event_listen().unwrap() {
+            Event::ClientMessage { args: received } => assert_eq!(received, args),
+            event => panic!("unexpected event {:?}", event),
+        }
+        drop(mpv);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn replies_longer_than_the_buffer_are_read_completely() {
+        let (mpv, peer) = connected_pair();
+        let huge: String = (0..1_500_000)
+            .map(|i| (b'a' + (i % 26) as u8) as char)
+            .collect();
+        let reply = huge.clone();
+        let server = serve(peer, move |_| {
+            vec![json!({"error": "success", "data": reply})]
+        });
+        assert_eq!(mpv.get_property::<String>("track-list").unwrap(), huge);
+        // the next reply starts on a line of its own
+        assert_eq!(mpv.get_property::<String>("track-list").unwrap(), huge);
+        drop(mpv);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn video_params_fixture() {
+        let (mpv, peer) = connected_pair();
+        let server = serve(peer, |request| match request["command"][1].as_str() {
+            // video-params of an H.264 file as mpv reports them
+            Some("video-params") => vec![json!({"error": "success", "data": {
+                "pixelformat": "yuv420p", "average-bpp": 12, "w": 1920, "h": 1080,
+                "dw": 1920, "dh": 1080, "aspect": 1.777778, "par": 1.0,
+                "colormatrix": "bt.709", "colorlevels": "limited", "primaries": "bt.709",
+                "gamma": "bt.1886", "sig-peak": 1.0, "light": "display",
+                "chroma-location": "mpeg2/4/h264", "rotate": 90, "stereo-in": "mono",
+                "alpha": "unknown"
+            }})],
+            Some("container-fps") => vec![json!({"error": "success", "data": 23.976})],
+            _ => vec![json!({"error": "property unavailable"})],
+        });
+        let video = mpv.video_params().unwrap();
+        assert_eq!((video.width, video.height), (Some(1920), Some(1080)));
+        assert_eq!(video.pixelformat.as_deref(), Some("yuv420p"));
+        assert_eq!(video.aspect, Some(1.777778));
+        assert_eq!(video.rotate, Some(90));
+        assert_eq!(video.fps, Some(23.976));
+        drop(mpv);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn video_params_of_an_audio_file() {
+        let (mpv, peer) = connected_pair();
+        let server = serve(peer, |_| vec![json!({"error": "property unavailable"})]);
+        let video = mpv.video_params().unwrap();
+        assert_eq!((video.width, video.height), (None, None));
+        assert_eq!(video.pixelformat, None);
+        assert_eq!((video.aspect, video.rotate, video.fps), (None, None, None));
+        drop(mpv);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn volume_steps_use_add() {
+        let (mpv, peer) = connected_pair();
+        let (commands, server) = record(peer);
+        mpv.volume_up(5.0).unwrap();
+        mpv.volume_down(2.5).unwrap();
+        assert!(matches!(
+            mpv.volume_up(f64::NAN),
+            Err(Error(ErrorCode::UnexpectedValue))
+        ));
+        assert_eq!(
+            *commands.lock().unwrap(),
+            [
+                json!(["add", "volume", "5"]),
+                json!(["add", "volume", "-2.5"])
+            ]
+        );
+        drop(mpv);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn observing_while_idle_reports_unavailable_first() {
+        let (mut mpv, peer) = connected_pair();
+        let mut events = peer.try_clone().unwrap();
+        // while idle mpv accepts the observation, but leaves out `data` as there is
+        // no duration yet
+        let server = serve(peer, |request| {
+            vec![
+                json!({"error": "success"}),
+                json!({"event": "property-change", "id": request["command"][1], "name": "duration"}),
+            ]
+        });
+        mpv.observe_property(1, "duration").unwrap();
+        assert!(matches!(
+            mpv.event_listen().unwrap(),
+            Event::PropertyChange {
+                id: 1,
+                property: Property::Duration(None)
+            }
+        ));
+
+        writeln!(
+            events,
+            "{}",
+            json!({"event": "property-change", "id": 1, "name": "duration", "data": 62.5})
+        )
+        .unwrap();
+        assert!(matches!(
+            mpv.event_listen().unwrap(),
+            Event::PropertyChange {
+                id: 1,
+                property: Property::Duration(Some(duration))
+            } if duration == 62.5
+        ));
+        drop(mpv);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn events_before_the_reply_are_skipped() {
+        let (mpv, peer) = connected_pair();
+        let server = serve(peer, |_| {
+            vec![
+                json!({"event": "idle"}),
+                json!({"event": "property-change", "id": 1, "name": "volume", "data": 30}),
+                json!({"error": "success", "data": 100.0}),
+            ]
+        });
+        assert_eq!(mpv.get_property::<f64>("volume").unwrap(), 100.0);
+        mpv.run_command(MpvCommand::Stop).unwrap();
+        drop(mpv);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn dispatch_pending_returns_without_waiting() {
+        let (mut mpv, mut peer) = connected_pair();
+        let mut dispatcher = EventDispatcher::new();
+        let pauses = std::cell::Cell::new(0);
+        let mut volumes = Vec::new();
+        {
+            let mut dispatcher = EventDispatcher::new();
+            dispatcher
+                .on_pause(|| pauses.set(pauses.get() + 1))
+                .on_property(1, |property| volumes.push(format!("{:?}", property)));
+            assert_eq!(dispatcher.dispatch_pending(&mut mpv).unwrap(), 0);
+
+            peer.write_all(b"{\"event\":\"pause\"}\n{\"request_id\":7,\"error\":\"success\"}\n")
+                .unwrap();
+            peer.write_all(b"{\"event\":\"property-change\",\"id\":1,\"name\":\"volume\",")
+                .unwrap();
+            assert_eq!(dispatcher.dispatch_pending(&mut mpv).unwrap(), 1);
+
+            // the rest of the cut off line is read the next time
+            peer.write_all(b"\"data\":50}\n").unwrap();
+            assert_eq!(dispatcher.dispatch_pending(&mut mpv).unwrap(), 1);
+        }
+        assert_eq!(pauses.get(), 1);
+        assert_eq!(volumes, ["Unknown { name: \"volume\", data: Usize(50) }"]);
+
+        drop(peer);
+        assert!(matches!(
+            dispatcher.dispatch_pending(&mut mpv),
+            Err(Error(ErrorCode::Disconnected))
+        ));
+    }
+
+    #[test]
+    fn dispatch_pending_keeps_events_before_an_error() {
+        let (mut mpv, mut peer) = connected_pair();
+        let mut count = 0;
+        peer.write_all(b"{\"event\":\"pause\"}\nnot json\n{\"event\":\"unpause\"}\n")
+            .unwrap();
+        let result = EventDispatcher::new()
+            .on_event(|_| count += 1)
+            .dispatch_pending(&mut mpv);
+        assert!(matches!(result, Err(Error(ErrorCode::JsonParseError(_)))));
+        assert_eq!(count, 2);
+    }
+
+    #[test]
+    fn dispatch_pending_leaves_the_socket_blocking() {
+        let (mut mpv, peer) = connected_pair();
+        EventDispatcher::new().dispatch_pending(&mut mpv).unwrap();
+        // a non-blocking socket would make the read fail instead of waiting
+        let server = serve(peer, |_| {
+            thread::sleep(Duration::from_millis(50));
+            vec![json!({"error": "success", "data": false})]
+        });
+        assert!(!mpv.get_property::<bool>("pause").unwrap());
+        drop(mpv);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn commands_keep_working_after_the_event_receiver_is_dropped() {
+        let (mpv, peer) = connected_pair();
+        let server = serve(peer, |_| {
+            vec![
+                json!({"event": "playback-restart"}),
+                json!({"error": "success", "data": false}),
+            ]
+        });
+        let (mpv, events) = mpv.into_event_thread().unwrap();
+        drop(events);
+
+        let clone = mpv.try_clone().unwrap();
+        for _ in 0..3 {
+            assert!(!clone.get_property::<bool>("pause").unwrap());
+        }
+        drop(clone);
+        drop(mpv);
+        server.join().unwrap();
+    }
+
+    /// Connects with the handshake to a socket `peer` plays the other side of.
+    fn handshake_with(
+        name: &str,
+        peer: impl FnOnce(UnixStream) + Send + 'static,
+    ) -> Result<Mpv, Error> {
+        let path =
+            std::env::temp_dir().join(format!("mpvipc-{}-{}.sock", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let listener = std::os::unix::net::UnixListener::bind(&path).unwrap();
+        let server = thread::spawn(move || peer(listener.accept().unwrap().0));
+        let connected = MpvBuilder::new(path.to_str().unwrap())
+            .handshake(true)
+            .connect();
+        server.join().unwrap();
+        let _ = std::fs::remove_file(&path);
+        connected
+    }
+
+    #[test]
+    fn handshake_rejects_peers_that_do_not_answer_like_mpv() {
+        let garbage = handshake_with("garbage", |mut peer| {
+            let mut request = String::new();
+            BufReader::new(peer.try_clone().unwrap())
+                .read_line(&mut request)
+                .unwrap();
+            peer.write_all(b"hello\n").unwrap();
+        });
+        let silent = handshake_with("silent", |peer| {
+            thread::sleep(HANDSHAKE_TIMEOUT + Duration::from_millis(500));
+            drop(peer);
+        });
+        for connected in [garbage, silent] {
+            assert!(matches!(
+                connected,
+                Err(Error(ErrorCode::ConnectError(ref why))) if why == "not an mpv IPC socket"
+            ));
+        }
+
+        let answered = handshake_with("mpv", |peer| {
+            serve(peer, |_| {
+                vec![json!({"error": "success", "data": "mpvipc"})]
+            });
+        });
+        assert!(answered.is_ok());
+    }
+
+    #[test]
+    fn handshake_passes_io_errors_through() {
+        let closed = handshake_with("closed", |peer| {
+            peer.shutdown(std::net::Shutdown::Both).unwrap();
+        });
+        assert!(matches!(
+            closed,
+            Err(Error(ErrorCode::Disconnected | ErrorCode::WriteError(_)))
+        ));
+    }
+
+    #[test]
+    fn ping_reports_whether_mpv_is_still_there() {
+        let (mpv, peer) = connected_pair();
+        let server = serve(peer, |_| {
+            vec![json!({"error": "success", "data": "mpvipc"})]
+        });
+        assert!(mpv.ping(Duration::from_secs(5)).is_ok());
+        drop(mpv);
+        server.join().unwrap();
+
+        let (mpv, peer) = connected_pair();
+        assert!(matches!(
+            mpv.ping(Duration::from_millis(50)),
+            Err(Error(ErrorCode::Timeout))
+        ));
+        drop(peer);
+        assert!(matches!(
+            mpv.ping(Duration::from_secs(5)),
+            Err(Error(ErrorCode::Disconnected | ErrorCode::WriteError(_)))
+        ));
+    }
+
+    #[test]
+    fn toggle_pause_sends_the_toggle_and_the_read_together() {
+        let (mpv, mut peer) = connected_pair();
+        let toggled = thread::spawn(move || mpv.toggle_pause());
+        let mut received = vec![0; 4096];
+        peer.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        let read = peer.read(&mut received).unwrap();
+        let lines: Vec<Value> = std::str::from_utf8(&received[..read])
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines[0]["command"], json!(["cycle", "pause"]));
+        assert_eq!(lines[1]["command"], json!(["get_property", "pause"]));
+
+        writeln!(
+            peer,
+            "{}",
+            json!({"request_id": lines[0]["request_id"], "error": "success"})
+        )
+        .unwrap();
+        writeln!(
+            peer,
+            "{}",
+            json!({"request_id": lines[1]["request_id"], "error": "success", "data": true})
+        )
+        .unwrap();
+        assert!(toggled.join().unwrap().unwrap());
+    }
+
+    #[test]
+    fn the_last_handle_shuts_the_connection_down() {
+        let (mpv, mut peer) = connected_pair();
+        let clone = mpv.try_clone().unwrap();
+        mpv.close().unwrap();
+        peer.set_read_timeout(Some(Duration::from_millis(50)))
+            .unwrap();
+        let still_open = peer.read(&mut [0; 1]).unwrap_err();
+        assert_eq!(still_open.kind(), std::io::ErrorKind::WouldBlock);
+
+        // clones dropped at the same time on different threads
+        let clones: Vec<Mpv> = (0..8).map(|_| clone.try_clone().unwrap()).collect();
+        drop(clone);
+        let dropped: Vec<_> = clones
+            .into_iter()
+            .map(|clone| thread::spawn(move || drop(clone)))
+            .collect();
+        for dropped in dropped {
+            dropped.join().unwrap();
+        }
+        peer.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        assert_eq!(peer.read(&mut [0; 1]).unwrap(), 0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line<T: IntoPropertyValue>(value: T) -> String {
+        set_property_command("volume", &value.into_property_value().unwrap()).to_string()
+    }
+
+    #[test]
+    fn set_property_json_per_type() {
+        let cases = [
+            (line(true), r#"true"#),
+            (line(String::from("a \"b\"\n")), r#""a \"b\"\n""#),
+            (line("ünïcode"), r#""ünïcode""#),
+            (line(1.25f64), "1.25"),
+            (line(0.1f32), "0.1"),
+            (line(-2.5f32), "-2.5"),
+            (line(50usize), "50"),
+            (line(-3i32), "-3"),
+            (line(i64::MIN), "-9223372036854775808"),
+            (line(u32::MAX), "4294967295"),
+        ];
+        for (line, value) in cases {
+            assert_eq!(
+                line,
+                format!(r#"{{"command":["set_property","volume",{}]}}"#, value)
+            );
+        }
+    }
+
+    #[test]
+    fn chapter_metadata_as_strings() {
+        let metadata = metadata_strings(json!({
+            "title": "Chapter 1",
+            "track": 3,
+            "gain": -1.5,
+            "explicit": false,
+        }));
+        let mut entries: Vec<(&str, &str)> = metadata
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+            .collect();
+        entries.sort();
+        assert_eq!(
+            entries,
+            [
+                ("explicit", "false"),
+                ("gain", "-1.5"),
+                ("title", "Chapter 1"),
+                ("track", "3"),
+            ]
+        );
+        assert!(metadata_strings(Value::Null).is_empty());
+    }
+
+    #[test]
+    fn non_finite_values_are_rejected() {
+        // JSON has no NaN or infinity
+        assert!(matches!(
+            f32::NAN.into_property_value(),
+            Err(Error(ErrorCode::UnexpectedValue))
+        ));
+        assert!(matches!(
+            f64::INFINITY.into_property_value(),
+            Err(Error(ErrorCode::UnexpectedValue))
+        ));
+    }
 }